@@ -0,0 +1,7 @@
+-- Role-based access control: every user is a USER unless promoted to ADMIN
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'USER';
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_check;
+ALTER TABLE users ADD CONSTRAINT users_role_check CHECK (role IN ('USER', 'ADMIN'));
+
+CREATE INDEX IF NOT EXISTS idx_users_role ON users(role);
@@ -0,0 +1,3 @@
+-- Token revocation: JWTs carry the version they were issued with and are
+-- rejected once it no longer matches (password reset, force logout, deactivation)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS token_version INTEGER NOT NULL DEFAULT 0;
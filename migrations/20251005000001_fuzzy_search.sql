@@ -0,0 +1,6 @@
+-- Trigram matching for typo-tolerant (fuzzy) search
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Serve `query <% column` word-similarity lookups in fuzzy search
+CREATE INDEX IF NOT EXISTS idx_notes_title_trgm ON notes USING GIN (title gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_notes_content_trgm ON notes USING GIN (content gin_trgm_ops);
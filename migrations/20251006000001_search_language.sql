@@ -0,0 +1,75 @@
+-- Text search language per user, with an optional override per note
+ALTER TABLE users ADD COLUMN IF NOT EXISTS language REGCONFIG NOT NULL DEFAULT 'english';
+ALTER TABLE notes ADD COLUMN IF NOT EXISTS language REGCONFIG;
+
+-- Only edits bump updated_at: re-indexing a note (such as a language
+-- switch) or other bookkeeping must not make it look recently edited.
+-- Statements that move or pin notes still set updated_at explicitly.
+CREATE OR REPLACE FUNCTION update_note_word_count()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.word_count = array_length(string_to_array(trim(NEW.content), ' '), 1);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS update_notes_updated_at ON notes;
+CREATE TRIGGER update_notes_updated_at
+    BEFORE UPDATE ON notes
+    FOR EACH ROW
+    WHEN (
+        OLD.title IS DISTINCT FROM NEW.title
+        OR OLD.content IS DISTINCT FROM NEW.content
+        OR OLD.language IS DISTINCT FROM NEW.language
+    )
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Effective configuration of each note: its override, else its owner's language
+ALTER TABLE notes ADD COLUMN IF NOT EXISTS search_language REGCONFIG NOT NULL DEFAULT 'english';
+
+UPDATE notes n
+SET search_language = COALESCE(n.language, u.language)
+FROM users u
+WHERE u.id = n.user_id;
+
+CREATE OR REPLACE FUNCTION set_note_search_language()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_language = COALESCE(
+        NEW.language,
+        (SELECT language FROM users WHERE id = NEW.user_id),
+        'english'
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_set_note_search_language ON notes;
+CREATE TRIGGER trigger_set_note_search_language
+    BEFORE INSERT OR UPDATE OF language, user_id ON notes
+    FOR EACH ROW
+    EXECUTE FUNCTION set_note_search_language();
+
+-- Re-stem notes without an override when their owner switches language
+CREATE OR REPLACE FUNCTION propagate_user_language()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE notes SET search_language = NEW.language
+    WHERE user_id = NEW.id AND language IS NULL;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_propagate_user_language ON users;
+CREATE TRIGGER trigger_propagate_user_language
+    AFTER UPDATE OF language ON users
+    FOR EACH ROW
+    WHEN (OLD.language IS DISTINCT FROM NEW.language)
+    EXECUTE FUNCTION propagate_user_language();
+
+-- Stored search document, built with the note's own configuration
+ALTER TABLE notes ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+    GENERATED ALWAYS AS (to_tsvector(search_language, title || ' ' || content)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_notes_search_vector ON notes USING GIN (search_vector);
+DROP INDEX IF EXISTS idx_notes_fts;
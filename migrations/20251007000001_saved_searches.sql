@@ -0,0 +1,19 @@
+-- Saved searches, shown to users as smart folders
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL CHECK (length(name) > 0 AND length(name) <= 100),
+    -- `searchNotes(q:)` query language, operators included
+    query TEXT NOT NULL CHECK (length(query) > 0 AND length(query) <= 500),
+    -- Only search this folder; the saved search goes away with it
+    folder_id UUID REFERENCES folders(id) ON DELETE CASCADE,
+    include_subfolders BOOLEAN NOT NULL DEFAULT FALSE,
+    fuzzy BOOLEAN NOT NULL DEFAULT FALSE,
+    color TEXT NOT NULL DEFAULT '#8B5CF6',
+    icon TEXT NOT NULL DEFAULT 'search',
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_saved_searches_user_id ON saved_searches(user_id, position);
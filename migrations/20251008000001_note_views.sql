@@ -0,0 +1,12 @@
+-- When each user last opened each note, for "recently viewed" lists.
+-- Rows are written in batches by the view flush job, not on every read.
+CREATE TABLE IF NOT EXISTS note_views (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    note_id UUID NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+    viewed_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, note_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_views_recent ON note_views(user_id, viewed_at DESC);
+
+UPDATE notes SET view_count = 0 WHERE view_count IS NULL;
@@ -0,0 +1,35 @@
+-- Append-only log of security-relevant actions: the user's activity feed and
+-- the admin audit trail. Rows are only ever inserted; the retention job and
+-- account deletion are the only ways they go away.
+CREATE TABLE IF NOT EXISTS audit_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Account the event belongs to (NULL for failed logins with an unknown email)
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    -- Who performed the action; an admin acting on the account, or the user.
+    -- No foreign key, so the trail survives the actor's account being deleted.
+    actor_id UUID,
+    action TEXT NOT NULL,
+    target_type TEXT,
+    target_id UUID,
+    ip_address TEXT,
+    user_agent TEXT,
+    -- Changed fields as {"field": {"from": ..., "to": ...}}
+    changes JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_user ON audit_events(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_events_actor ON audit_events(actor_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_events_action ON audit_events(action, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_events_created_at ON audit_events(created_at);
+
+CREATE OR REPLACE FUNCTION audit_events_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_events_no_update ON audit_events;
+CREATE TRIGGER audit_events_no_update
+    BEFORE UPDATE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();
@@ -0,0 +1,16 @@
+-- Reusable note skeletons with {{placeholder}} markers (see src/templates.rs).
+-- Shared templates can be used by every user; only the owner can change them.
+CREATE TABLE IF NOT EXISTS note_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL CHECK (char_length(name) BETWEEN 1 AND 100),
+    description TEXT,
+    title_template TEXT CHECK (char_length(title_template) <= 200),
+    content_template TEXT NOT NULL,
+    is_shared BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_templates_user ON note_templates(user_id, name);
+CREATE INDEX IF NOT EXISTS idx_note_templates_shared ON note_templates(name) WHERE is_shared;
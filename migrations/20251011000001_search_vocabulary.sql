@@ -0,0 +1,72 @@
+-- Words of each user's notes (like ts_stat over their notes), kept up to date
+-- by triggers so did-you-mean suggestions are a trigram index lookup instead of
+-- splitting every note on each search without exact matches.
+CREATE TABLE IF NOT EXISTS search_vocabulary (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    word TEXT NOT NULL,
+    -- Number of the user's notes containing the word
+    note_count INTEGER NOT NULL,
+    PRIMARY KEY (user_id, word)
+);
+
+-- Serve `word % term` lookups for suggestions
+CREATE INDEX IF NOT EXISTS idx_search_vocabulary_word_trgm
+    ON search_vocabulary USING GIN (word gin_trgm_ops);
+
+-- Distinct words of a note, split the same way for every caller
+CREATE OR REPLACE FUNCTION note_vocabulary(title TEXT, content TEXT)
+RETURNS SETOF TEXT AS $$
+    SELECT DISTINCT word
+    FROM regexp_split_to_table(lower(coalesce(title, '') || ' ' || coalesce(content, '')), '[^[:alnum:]]+') AS word
+    WHERE length(word) > 2
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION sync_search_vocabulary()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') AND OLD.user_id IS NOT NULL THEN
+        UPDATE search_vocabulary v
+        SET note_count = v.note_count - 1
+        FROM note_vocabulary(OLD.title, OLD.content) AS w(word)
+        WHERE v.user_id = OLD.user_id AND v.word = w.word;
+
+        DELETE FROM search_vocabulary
+        WHERE user_id = OLD.user_id AND note_count <= 0;
+    END IF;
+
+    IF TG_OP IN ('INSERT', 'UPDATE') AND NEW.user_id IS NOT NULL THEN
+        INSERT INTO search_vocabulary (user_id, word, note_count)
+        SELECT NEW.user_id, word, 1
+        FROM note_vocabulary(NEW.title, NEW.content) AS word
+        ON CONFLICT (user_id, word)
+        DO UPDATE SET note_count = search_vocabulary.note_count + 1;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS sync_search_vocabulary_insert_delete ON notes;
+CREATE TRIGGER sync_search_vocabulary_insert_delete
+    AFTER INSERT OR DELETE ON notes
+    FOR EACH ROW
+    EXECUTE FUNCTION sync_search_vocabulary();
+
+DROP TRIGGER IF EXISTS sync_search_vocabulary_update ON notes;
+CREATE TRIGGER sync_search_vocabulary_update
+    AFTER UPDATE OF title, content, user_id ON notes
+    FOR EACH ROW
+    WHEN (
+        OLD.title IS DISTINCT FROM NEW.title
+        OR OLD.content IS DISTINCT FROM NEW.content
+        OR OLD.user_id IS DISTINCT FROM NEW.user_id
+    )
+    EXECUTE FUNCTION sync_search_vocabulary();
+
+-- Vocabulary of the existing notes
+INSERT INTO search_vocabulary (user_id, word, note_count)
+SELECT n.user_id, w.word, count(*)
+FROM notes n, note_vocabulary(n.title, n.content) AS w(word)
+WHERE n.user_id IS NOT NULL
+GROUP BY n.user_id, w.word
+ON CONFLICT (user_id, word) DO NOTHING;
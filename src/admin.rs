@@ -0,0 +1,203 @@
+//! # Admin GraphQL Namespace
+//!
+//! Operations reserved for users with the `ADMIN` role. The namespace is
+//! mounted as the `admin` field on the query and mutation roots and protected
+//! by a [`RoleGuard`](crate::auth::RoleGuard), so every field below it is only
+//! reachable by administrators.
+
+use async_graphql::{Context, Object, Result};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::audit::{self, AuditAction, AuditRecord, AuditTargetType};
+use crate::auth::{require_auth, AdminResetPasswordInput, AuthService, User};
+use crate::database::{AuditEventFilters, Database, NoteFilters};
+use crate::errors::AppError;
+use crate::resolvers::{parse_optional_timestamp, parse_optional_uuid};
+use crate::types::{AuditEvent, AuditEventFilter, Note, NoteOrderBy, SystemStats};
+
+/// Default page size for admin listings
+const DEFAULT_PAGE_SIZE: i32 = 50;
+/// Maximum page size for admin listings
+const MAX_PAGE_SIZE: i32 = 500;
+
+/// 👑 Admin-only queries
+pub struct AdminQuery;
+
+#[Object]
+impl AdminQuery {
+    /// 👥 List all users (newest first)
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<User>> {
+        let db = ctx.data::<Database>()?;
+        let (limit, offset) = page(limit, offset);
+
+        let users = db.list_users(limit, offset).await?;
+        Ok(users.into_iter().map(User::from).collect())
+    }
+
+    /// 📚 Get notes across all tenants, optionally for a single user
+    async fn all_notes(&self, ctx: &Context<'_>, user_id: Option<String>) -> Result<Vec<Note>> {
+        let db = ctx.data::<Database>()?;
+
+        let notes = match user_id {
+            Some(id) => {
+                let user_uuid = parse_user_id(&id)?;
+                db.get_user_notes(user_uuid, &NoteFilters::default(), NoteOrderBy::default())
+                    .await?
+            }
+            None => db.get_all_notes().await?,
+        };
+        Ok(notes)
+    }
+
+    /// 🕵️ Audit log across all users, newest first
+    async fn audit_events(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<AuditEventFilter>,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<AuditEvent>> {
+        let filter = filter.unwrap_or_default();
+        filter.validate().map_err(AppError::from)?;
+        let db = ctx.data::<Database>()?;
+        let (limit, offset) = page(limit, offset);
+
+        let filters = AuditEventFilters {
+            user_id: parse_optional_uuid(filter.user_id.as_deref())?,
+            actor_id: parse_optional_uuid(filter.actor_id.as_deref())?,
+            actions: filter.actions,
+            target_type: filter.target_type,
+            target_id: parse_optional_uuid(filter.target_id.as_deref())?,
+            ip_address: filter.ip_address,
+            after: parse_optional_timestamp("after", filter.after.as_deref())?,
+            before: parse_optional_timestamp("before", filter.before.as_deref())?,
+        };
+        let events = db.get_audit_events(&filters, limit, offset).await?;
+        Ok(events)
+    }
+
+    /// 📊 System-wide statistics
+    async fn stats(&self, ctx: &Context<'_>) -> Result<SystemStats> {
+        let db = ctx.data::<Database>()?;
+        let stats = db.get_system_stats().await?;
+        Ok(stats)
+    }
+}
+
+/// 👑 Admin-only mutations
+pub struct AdminMutation;
+
+#[Object]
+impl AdminMutation {
+    /// 🚫 Deactivate a user; they can no longer log in and their tokens stop working
+    async fn deactivate_user(&self, ctx: &Context<'_>, user_id: String) -> Result<User> {
+        let (admin_id, _admin) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let user_uuid = parse_user_id(&user_id)?;
+        if user_uuid == admin_id {
+            return Err(AppError::ValidationError {
+                message: "Administrators cannot deactivate their own account".to_string(),
+            }
+            .into());
+        }
+
+        let user = db
+            .set_user_active(user_uuid, false)
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+        audit::record(
+            ctx,
+            admin_action(AuditAction::UserDeactivated, admin_id, user_uuid),
+        )
+        .await;
+        Ok(User::from(user))
+    }
+
+    /// ✅ Reactivate a previously deactivated user
+    async fn reactivate_user(&self, ctx: &Context<'_>, user_id: String) -> Result<User> {
+        let (admin_id, _admin) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let user_uuid = parse_user_id(&user_id)?;
+        let user = db
+            .set_user_active(user_uuid, true)
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+        audit::record(
+            ctx,
+            admin_action(AuditAction::UserReactivated, admin_id, user_uuid),
+        )
+        .await;
+        Ok(User::from(user))
+    }
+
+    /// 🔑 Set a new password for a user and revoke all of their tokens
+    async fn reset_user_password(
+        &self,
+        ctx: &Context<'_>,
+        input: AdminResetPasswordInput,
+    ) -> Result<bool> {
+        let (admin_id, _admin) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
+
+        let db = ctx.data::<Database>()?;
+        let auth = ctx.data::<AuthService>()?;
+
+        let user_uuid = parse_user_id(&input.user_id)?;
+        let password_hash = auth.hash_password(&input.new_password)?;
+        db.update_user_password(user_uuid, &password_hash)
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+        audit::record(
+            ctx,
+            admin_action(AuditAction::PasswordReset, admin_id, user_uuid),
+        )
+        .await;
+        Ok(true)
+    }
+
+    /// 🚪 Force logout: revoke every token issued to a user
+    async fn revoke_user_tokens(&self, ctx: &Context<'_>, user_id: String) -> Result<bool> {
+        let (admin_id, _admin) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let user_uuid = parse_user_id(&user_id)?;
+        if !db.revoke_user_tokens(user_uuid).await? {
+            return Err(AppError::UserNotFound.into());
+        }
+        audit::record(
+            ctx,
+            admin_action(AuditAction::TokensRevoked, admin_id, user_uuid),
+        )
+        .await;
+        Ok(true)
+    }
+}
+
+/// Audit event for an administrator acting on a user's account
+fn admin_action(action: AuditAction, admin_id: Uuid, user_id: Uuid) -> AuditRecord {
+    AuditRecord::new(action, user_id)
+        .actor(admin_id)
+        .target(AuditTargetType::User, &user_id.to_string())
+}
+
+/// Parse a user ID argument
+fn parse_user_id(id: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(id).map_err(|_| AppError::InvalidUuid {
+        uuid: id.to_string(),
+    })
+}
+
+/// Clamp pagination arguments to sane bounds
+fn page(limit: Option<i32>, offset: Option<i32>) -> (i64, i64) {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = offset.unwrap_or(0).max(0);
+    (limit as i64, offset as i64)
+}
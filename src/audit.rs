@@ -0,0 +1,295 @@
+//! # Audit Log
+//!
+//! Security-relevant actions are appended to the `audit_events` table: logins
+//! and failed logins, registration, password and email changes, account
+//! deletion requests, note and folder changes, and the admin operations on
+//! user accounts. Each event records the account it belongs to, the actor
+//! (an admin acting on the account, or the user themselves), the client's IP
+//! address and user agent, and for changes a diff of the affected fields.
+//!
+//! Users read their own events through `myActivity`; administrators query all
+//! of them through `admin { auditEvents }`. Events older than
+//! `accounts.audit_retention_days` are pruned by
+//! [`crate::jobs::spawn_audit_prune`].
+//!
+//! An event is written after the action succeeded. A failed write is logged
+//! and does not fail the request, since the action itself already happened.
+
+use async_graphql::{Context, Enum};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::types::{Folder, Note};
+use crate::web::ClientInfo;
+
+/// Longest text value kept in a diff; longer values are cut off
+const MAX_DIFF_VALUE_CHARS: usize = 200;
+
+/// 🕵️ What happened
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Login,
+    LoginFailed,
+    Registered,
+    PasswordChanged,
+    EmailChangeRequested,
+    EmailChanged,
+    AccountDeletionRequested,
+    AccountDeletionCancelled,
+    NoteCreated,
+    NoteUpdated,
+    NoteDeleted,
+    FolderCreated,
+    FolderUpdated,
+    FolderDeleted,
+    /// An administrator deactivated the account
+    UserDeactivated,
+    /// An administrator reactivated the account
+    UserReactivated,
+    /// An administrator set a new password
+    PasswordReset,
+    /// An administrator revoked every token of the account
+    TokensRevoked,
+}
+
+impl AuditAction {
+    const ALL: [AuditAction; 18] = [
+        AuditAction::Login,
+        AuditAction::LoginFailed,
+        AuditAction::Registered,
+        AuditAction::PasswordChanged,
+        AuditAction::EmailChangeRequested,
+        AuditAction::EmailChanged,
+        AuditAction::AccountDeletionRequested,
+        AuditAction::AccountDeletionCancelled,
+        AuditAction::NoteCreated,
+        AuditAction::NoteUpdated,
+        AuditAction::NoteDeleted,
+        AuditAction::FolderCreated,
+        AuditAction::FolderUpdated,
+        AuditAction::FolderDeleted,
+        AuditAction::UserDeactivated,
+        AuditAction::UserReactivated,
+        AuditAction::PasswordReset,
+        AuditAction::TokensRevoked,
+    ];
+
+    /// Database representation of the action
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Login => "LOGIN",
+            AuditAction::LoginFailed => "LOGIN_FAILED",
+            AuditAction::Registered => "REGISTERED",
+            AuditAction::PasswordChanged => "PASSWORD_CHANGED",
+            AuditAction::EmailChangeRequested => "EMAIL_CHANGE_REQUESTED",
+            AuditAction::EmailChanged => "EMAIL_CHANGED",
+            AuditAction::AccountDeletionRequested => "ACCOUNT_DELETION_REQUESTED",
+            AuditAction::AccountDeletionCancelled => "ACCOUNT_DELETION_CANCELLED",
+            AuditAction::NoteCreated => "NOTE_CREATED",
+            AuditAction::NoteUpdated => "NOTE_UPDATED",
+            AuditAction::NoteDeleted => "NOTE_DELETED",
+            AuditAction::FolderCreated => "FOLDER_CREATED",
+            AuditAction::FolderUpdated => "FOLDER_UPDATED",
+            AuditAction::FolderDeleted => "FOLDER_DELETED",
+            AuditAction::UserDeactivated => "USER_DEACTIVATED",
+            AuditAction::UserReactivated => "USER_REACTIVATED",
+            AuditAction::PasswordReset => "PASSWORD_RESET",
+            AuditAction::TokensRevoked => "TOKENS_REVOKED",
+        }
+    }
+
+    /// Parse the database representation
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.as_str() == value)
+    }
+}
+
+/// 🎯 Kind of object an event is about
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditTargetType {
+    User,
+    Note,
+    Folder,
+}
+
+impl AuditTargetType {
+    /// Database representation of the target type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditTargetType::User => "USER",
+            AuditTargetType::Note => "NOTE",
+            AuditTargetType::Folder => "FOLDER",
+        }
+    }
+
+    /// Parse the database representation
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "USER" => Some(AuditTargetType::User),
+            "NOTE" => Some(AuditTargetType::Note),
+            "FOLDER" => Some(AuditTargetType::Folder),
+            _ => None,
+        }
+    }
+}
+
+/// 📝 An event to append to the audit log
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    /// Account the event belongs to
+    pub user_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target: Option<(AuditTargetType, Uuid)>,
+    pub changes: Option<Value>,
+}
+
+impl AuditRecord {
+    /// An action the user performed on their own account
+    pub fn new(action: AuditAction, user_id: Uuid) -> Self {
+        Self {
+            user_id: Some(user_id),
+            actor_id: Some(user_id),
+            action,
+            target: None,
+            changes: None,
+        }
+    }
+
+    /// An action without a known account, such as a login with an unknown email
+    pub fn anonymous(action: AuditAction) -> Self {
+        Self {
+            user_id: None,
+            actor_id: None,
+            action,
+            target: None,
+            changes: None,
+        }
+    }
+
+    /// Someone else (an administrator) performed the action
+    pub fn actor(mut self, actor_id: Uuid) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    /// The object the action was applied to; IDs that are not UUIDs are dropped
+    pub fn target(mut self, target_type: AuditTargetType, id: &str) -> Self {
+        self.target = Uuid::parse_str(id).ok().map(|id| (target_type, id));
+        self
+    }
+
+    pub fn changes(mut self, changes: Option<Value>) -> Self {
+        self.changes = changes;
+        self
+    }
+}
+
+/// Append an event, attributed to the client of the current request
+pub async fn record(ctx: &Context<'_>, event: AuditRecord) {
+    let Ok(db) = ctx.data::<Database>() else {
+        return;
+    };
+    let client = ctx.data_opt::<ClientInfo>().cloned().unwrap_or_default();
+
+    if let Err(e) = db
+        .record_audit_event(
+            &event,
+            client.ip_address.as_deref(),
+            client.user_agent.as_deref(),
+        )
+        .await
+    {
+        tracing::error!(error = %e, action = event.action.as_str(), "Failed to record audit event");
+    }
+}
+
+/// Fields of `before` and `after` (JSON objects) that differ, as
+/// `{"field": {"from": old, "to": new}}`; `None` when nothing changed
+///
+/// A field missing on one side counts as `null`, so diffing against `{}`
+/// describes a created or deleted object.
+pub fn diff(before: &Value, after: &Value) -> Option<Value> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        if changes.contains_key(key) {
+            continue;
+        }
+        let from = before.get(key).unwrap_or(&Value::Null);
+        let to = after.get(key).unwrap_or(&Value::Null);
+        if from != to {
+            changes.insert(
+                key.clone(),
+                json!({ "from": truncate(from), "to": truncate(to) }),
+            );
+        }
+    }
+
+    (!changes.is_empty()).then_some(Value::Object(changes))
+}
+
+/// Cut long text values down to [`MAX_DIFF_VALUE_CHARS`]
+fn truncate(value: &Value) -> Value {
+    match value {
+        Value::String(text) if text.chars().count() > MAX_DIFF_VALUE_CHARS => {
+            let mut text: String = text.chars().take(MAX_DIFF_VALUE_CHARS).collect();
+            text.push('…');
+            Value::String(text)
+        }
+        value => value.clone(),
+    }
+}
+
+/// Audited fields of a note
+pub fn note_snapshot(note: &Note) -> Value {
+    json!({
+        "title": note.title,
+        "content": note.content,
+        "language": note.language,
+        "folderId": note.folder.as_ref().map(|folder| &folder.id),
+    })
+}
+
+/// Audited fields of a folder
+pub fn folder_snapshot(folder: &Folder) -> Value {
+    json!({
+        "name": folder.name,
+        "description": folder.description,
+        "color": folder.color,
+        "icon": folder.icon,
+        "position": folder.position,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_changed_fields_only() {
+        let long = "x".repeat(MAX_DIFF_VALUE_CHARS + 10);
+        let before = json!({ "title": "Old", "content": "same", "language": null });
+        let after = json!({ "title": "New", "content": "same", "language": long });
+
+        let changes = diff(&before, &after).unwrap();
+        assert_eq!(changes["title"], json!({ "from": "Old", "to": "New" }));
+        assert!(changes.get("content").is_none());
+        assert_eq!(
+            changes["language"]["to"].as_str().unwrap().chars().count(),
+            MAX_DIFF_VALUE_CHARS + 1
+        );
+
+        assert_eq!(diff(&before, &before), None);
+        assert_eq!(
+            diff(&json!({}), &json!({ "name": "Work" })),
+            Some(json!({ "name": { "from": null, "to": "Work" } }))
+        );
+    }
+}
@@ -2,34 +2,79 @@
 //!
 //! JWT-based authentication system with bcrypt password hashing
 
-use async_graphql::{Context, InputObject, SimpleObject};
-use bcrypt::{hash, verify, DEFAULT_COST};
+use async_graphql::{
+    Context, Enum, ErrorExtensions, Guard, InputObject, Result as GraphQLResult, SimpleObject,
+};
+use bcrypt::{hash, verify};
 use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::config::AuthConfig;
 use crate::errors::{AppError, AppResult};
 
+/// 👑 User role stored in `users.role`
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UserRole {
+    /// Regular user with access to their own data only
+    #[default]
+    User,
+    /// Administrator with access to the admin namespace
+    Admin,
+}
+
+impl UserRole {
+    /// Database representation of the role
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::User => "USER",
+            UserRole::Admin => "ADMIN",
+        }
+    }
+
+    /// Parse the database representation (unknown values fall back to `USER`)
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "ADMIN" => UserRole::Admin,
+            _ => UserRole::User,
+        }
+    }
+}
+
+impl From<String> for UserRole {
+    fn from(value: String) -> Self {
+        UserRole::from_db(&value)
+    }
+}
+
 /// JWT Claims structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String, // User ID
     pub email: String,
+    #[serde(default)]
+    pub role: UserRole, // Role at the time the token was issued
+    #[serde(default)]
+    pub ver: i32, // User token version, bumped to revoke all issued tokens
     pub exp: i64, // Expiration timestamp
     pub iat: i64, // Issued at timestamp
 }
 
 impl Claims {
-    /// Create new claims for a user
-    pub fn new(user_id: Uuid, email: String) -> Self {
+    /// Create new claims for a user, valid for `ttl_hours`
+    pub fn new(user: &UserRow, ttl_hours: i64) -> Self {
         let now = Utc::now();
-        let exp = now + Duration::hours(24); // Token valid for 24 hours
+        let exp = now + Duration::hours(ttl_hours);
 
         Self {
-            sub: user_id.to_string(),
-            email,
+            sub: user.id.to_string(),
+            email: user.email.clone(),
+            role: user.role,
+            ver: user.token_version,
             exp: exp.timestamp(),
             iat: now.timestamp(),
         }
@@ -72,6 +117,47 @@ impl AuthContext {
     pub fn require_user(&self) -> AppResult<&UserRow> {
         self.user.as_ref().ok_or(AppError::Unauthorized)
     }
+
+    /// Check whether the authenticated user has the given role
+    pub fn has_role(&self, role: UserRole) -> bool {
+        self.user.as_ref().is_some_and(|user| user.role == role)
+    }
+}
+
+/// 🛡️ Resolver guard that only lets users with a specific role through
+///
+/// The role is taken from the user row loaded by the JWT middleware rather
+/// than from the token claims, so demoting an admin takes effect immediately.
+///
+/// ```ignore
+/// #[graphql(guard = "RoleGuard::new(UserRole::Admin)")]
+/// async fn admin(&self) -> AdminQuery { AdminQuery }
+/// ```
+pub struct RoleGuard {
+    role: UserRole,
+}
+
+impl RoleGuard {
+    /// Create a guard requiring `role`
+    pub fn new(role: UserRole) -> Self {
+        Self { role }
+    }
+}
+
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> GraphQLResult<()> {
+        let auth_ctx = get_auth_context(ctx).map_err(|e| e.extend())?;
+
+        if !auth_ctx.is_authenticated {
+            return Err(AppError::Unauthorized.extend());
+        }
+
+        if auth_ctx.has_role(self.role) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden.extend())
+        }
+    }
 }
 
 /// User registration input
@@ -91,6 +177,50 @@ pub struct RegisterInput {
     pub full_name: Option<String>,
 }
 
+/// 👤 Profile update input
+#[derive(InputObject, Validate)]
+pub struct UpdateProfileInput {
+    #[validate(length(
+        min = 2,
+        max = 100,
+        message = "Full name must be between 2 and 100 characters"
+    ))]
+    pub full_name: Option<String>,
+
+    /// Text search configuration for your notes, one of `searchLanguages`
+    #[validate(length(min = 1, max = 63, message = "Language cannot be empty"))]
+    pub language: Option<String>,
+}
+
+/// 🔑 Password change input
+#[derive(InputObject, Validate)]
+pub struct ChangePasswordInput {
+    pub current_password: String,
+
+    #[validate(length(min = 8, message = "Password must be at least 8 characters long"))]
+    pub new_password: String,
+}
+
+/// 📧 Email change input
+#[derive(InputObject, Validate)]
+pub struct ChangeEmailInput {
+    #[validate(email(message = "Invalid email format"))]
+    pub new_email: String,
+
+    /// Current password, required to confirm the change
+    pub password: String,
+}
+
+/// 👑 Admin password reset input
+#[derive(InputObject, Validate)]
+pub struct AdminResetPasswordInput {
+    /// ID of the user whose password is reset
+    pub user_id: String,
+
+    #[validate(length(min = 8, message = "Password must be at least 8 characters long"))]
+    pub new_password: String,
+}
+
 /// User login input
 #[derive(InputObject, Validate)]
 pub struct LoginInput {
@@ -115,6 +245,11 @@ pub struct User {
     pub created_at: String,
     pub updated_at: String,
     pub is_active: bool,
+    pub role: UserRole,
+    /// When the account will be permanently deleted, if deletion was requested
+    pub deletion_scheduled_at: Option<String>,
+    /// Text search configuration for the user's notes (e.g. `english`, `german`)
+    pub language: String,
 }
 
 /// Database user row helper
@@ -127,6 +262,11 @@ pub struct UserRow {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub is_active: bool,
+    #[sqlx(try_from = "String")]
+    pub role: UserRole,
+    pub token_version: i32,
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+    pub language: String,
 }
 
 impl From<UserRow> for User {
@@ -138,6 +278,9 @@ impl From<UserRow> for User {
             created_at: row.created_at.to_rfc3339(),
             updated_at: row.updated_at.to_rfc3339(),
             is_active: row.is_active,
+            role: row.role,
+            deletion_scheduled_at: row.deletion_scheduled_at.map(|dt| dt.to_rfc3339()),
+            language: row.language,
         }
     }
 }
@@ -146,20 +289,23 @@ impl From<UserRow> for User {
 #[derive(Clone)] // ✅ Add Clone trait here too
 pub struct AuthService {
     jwt_secret: String,
+    token_ttl_hours: i64,
+    bcrypt_cost: u32,
 }
 
 impl AuthService {
-    /// Create new auth service
-    pub fn new() -> Self {
-        let jwt_secret = std::env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "your-super-secret-jwt-key-change-in-production".to_string());
-
-        Self { jwt_secret }
+    /// Create new auth service from the `[auth]` settings
+    pub fn new(config: &AuthConfig) -> Self {
+        Self {
+            jwt_secret: config.jwt_secret.clone(),
+            token_ttl_hours: config.token_ttl_hours,
+            bcrypt_cost: config.bcrypt_cost,
+        }
     }
 
     /// Hash password using bcrypt
     pub fn hash_password(&self, password: &str) -> AppResult<String> {
-        hash(password, DEFAULT_COST).map_err(|e| AppError::AuthError {
+        hash(password, self.bcrypt_cost).map_err(|e| AppError::AuthError {
             message: format!("Failed to hash password: {}", e),
         })
     }
@@ -171,9 +317,24 @@ impl AuthService {
         })
     }
 
+    /// Generate a random single-use token and the hash to store for it
+    ///
+    /// Only the hash is persisted, so a leaked database row cannot be
+    /// replayed as a valid token.
+    pub fn generate_opaque_token(&self) -> (String, String) {
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let token_hash = Self::hash_opaque_token(&token);
+        (token, token_hash)
+    }
+
+    /// Hash a single-use token for lookup
+    pub fn hash_opaque_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
     /// Generate JWT token
-    pub fn generate_token(&self, user_id: Uuid, email: String) -> AppResult<String> {
-        let claims = Claims::new(user_id, email);
+    pub fn generate_token(&self, user: &UserRow) -> AppResult<String> {
+        let claims = Claims::new(user, self.token_ttl_hours);
         let header = Header::default();
         let encoding_key = EncodingKey::from_secret(self.jwt_secret.as_ref());
 
@@ -194,15 +355,6 @@ impl AuthService {
             })
     }
 
-    /// Extract user ID from authorization header token
-    pub fn extract_user_id_from_token(&self, token: &str) -> AppResult<Uuid> {
-        let claims = self.verify_token(token)?;
-
-        Uuid::parse_str(&claims.sub).map_err(|_| AppError::AuthError {
-            message: "Invalid user ID in token".to_string(),
-        })
-    }
-
     /// 🔐 Create auth context from Authorization header
     pub async fn create_auth_context(
         &self,
@@ -229,10 +381,17 @@ impl AuthService {
         };
 
         // Get user from database
-        match db.get_user_by_id(user_id).await {
-            Ok(Some(user)) => AuthContext::authenticated(user_id, user),
-            _ => AuthContext::unauthenticated(),
+        let user = match db.get_user_by_id(user_id).await {
+            Ok(Some(user)) => user,
+            _ => return AuthContext::unauthenticated(),
+        };
+
+        // Reject deactivated accounts and tokens issued before a revocation
+        if !user.is_active || user.token_version != claims.ver {
+            return AuthContext::unauthenticated();
         }
+
+        AuthContext::authenticated(user_id, user)
     }
 }
 
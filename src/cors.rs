@@ -0,0 +1,351 @@
+//! # Cross-Origin Resource Sharing
+//!
+//! Builds the CORS layer from the `[cors]` configuration section.
+//!
+//! ## Origin Patterns
+//!
+//! - `https://app.example.com`: exact origin (scheme, host and port must match)
+//! - `https://*.example.com`: any subdomain of `example.com` over HTTPS, at any
+//!   depth, but not `example.com` itself
+//! - `*`: any origin (cannot be combined with credentials)
+//!
+//! Browsers enforce the policy; the server only decides which CORS headers to
+//! send. Preflight requests the policy rejects are logged so misconfigured
+//! frontends show up in the server logs rather than only in a browser console.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+
+/// 🎯 A single allowed-origin entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OriginPattern {
+    /// `*`
+    Any,
+    /// `https://app.example.com[:port]`
+    Exact(String),
+    /// `https://*.example.com[:port]`, stored as the scheme and `.example.com[:port]`
+    Subdomain { scheme: String, suffix: String },
+}
+
+impl OriginPattern {
+    /// Whether a request `Origin` header value matches this pattern
+    pub fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+
+        match self {
+            OriginPattern::Any => true,
+            OriginPattern::Exact(allowed) => origin == *allowed,
+            OriginPattern::Subdomain { scheme, suffix } => {
+                let Some(host) = origin.strip_prefix(scheme.as_str()) else {
+                    return false;
+                };
+                let Some(subdomain) = host.strip_suffix(suffix.as_str()) else {
+                    return false;
+                };
+
+                !subdomain.is_empty()
+                    && !subdomain.starts_with('.')
+                    && !subdomain.ends_with('.')
+                    && subdomain
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+            }
+        }
+    }
+}
+
+impl FromStr for OriginPattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "*" {
+            return Ok(OriginPattern::Any);
+        }
+
+        let value = value.to_ascii_lowercase();
+        let scheme = ["https://", "http://"]
+            .into_iter()
+            .find(|scheme| value.starts_with(scheme))
+            .ok_or_else(|| format!("{:?} must start with http:// or https://", value))?;
+
+        let authority = &value[scheme.len()..];
+        if authority.is_empty() {
+            return Err(format!("{:?} has no host", value));
+        }
+        if authority.contains(['/', '?', '#', '@']) {
+            return Err(format!(
+                "{:?} must be an origin without path, query or credentials",
+                value
+            ));
+        }
+
+        match authority.strip_prefix('*') {
+            Some(suffix)
+                if suffix.starts_with('.') && suffix.len() > 1 && !suffix.contains('*') =>
+            {
+                Ok(OriginPattern::Subdomain {
+                    scheme: scheme.to_string(),
+                    suffix: suffix.to_string(),
+                })
+            }
+            Some(_) => Err(format!(
+                "{:?}: a wildcard must be followed by a domain, as in https://*.example.com",
+                value
+            )),
+            None if authority.contains('*') => Err(format!(
+                "{:?}: wildcards are only supported as the leftmost label",
+                value
+            )),
+            None => Ok(OriginPattern::Exact(value)),
+        }
+    }
+}
+
+/// 🌍 Parsed CORS policy shared by the CORS layer and preflight logging
+#[derive(Clone)]
+pub struct CorsPolicy {
+    inner: Arc<PolicyInner>,
+}
+
+struct PolicyInner {
+    origins: Vec<OriginPattern>,
+    methods: Vec<Method>,
+    headers: Vec<HeaderName>,
+    exposed_headers: Vec<HeaderName>,
+    allow_credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl CorsPolicy {
+    /// Parse the `[cors]` settings, returning every invalid entry on failure
+    pub fn from_config(config: &CorsConfig) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+
+        let origins = parse_all(&config.allowed_origins, "cors.allowed_origins", &mut errors);
+        let methods = parse_all(&config.allowed_methods, "cors.allowed_methods", &mut errors);
+        let headers = parse_all(&config.allowed_headers, "cors.allowed_headers", &mut errors);
+        let exposed_headers =
+            parse_all(&config.exposed_headers, "cors.exposed_headers", &mut errors);
+
+        if config.allow_credentials && origins.contains(&OriginPattern::Any) {
+            errors.push(
+                "cors.allow_credentials cannot be combined with the \"*\" origin".to_string(),
+            );
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(Self {
+            inner: Arc::new(PolicyInner {
+                origins,
+                methods,
+                headers,
+                exposed_headers,
+                allow_credentials: config.allow_credentials,
+                max_age: Some(config.max_age_secs)
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs),
+            }),
+        })
+    }
+
+    /// Whether cross-origin requests from `origin` are allowed
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.inner
+            .origins
+            .iter()
+            .any(|pattern| pattern.matches(origin))
+    }
+
+    /// Build the tower-http CORS layer enforcing this policy
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = if self.inner.origins.contains(&OriginPattern::Any) {
+            AllowOrigin::any()
+        } else {
+            let policy = self.clone();
+            AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .map(|origin| policy.allows_origin(origin))
+                    .unwrap_or(false)
+            })
+        };
+
+        let layer = CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(self.inner.methods.clone())
+            .allow_headers(self.inner.headers.clone())
+            .expose_headers(self.inner.exposed_headers.clone())
+            .allow_credentials(self.inner.allow_credentials);
+
+        match self.inner.max_age {
+            Some(max_age) => layer.max_age(max_age),
+            None => layer,
+        }
+    }
+
+    /// Why a preflight request would be rejected, or `None` if it is allowed
+    fn preflight_rejection(&self, origin: &str, request: &Request) -> Option<String> {
+        if !self.allows_origin(origin) {
+            return Some("origin not allowed".to_string());
+        }
+
+        let method = request
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if !self
+            .inner
+            .methods
+            .iter()
+            .any(|allowed| allowed.as_str().eq_ignore_ascii_case(method))
+        {
+            return Some(format!("method {} not allowed", method));
+        }
+
+        let requested_headers = request
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let rejected_headers = requested_headers
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .filter(|name| {
+                !self
+                    .inner
+                    .headers
+                    .iter()
+                    .any(|allowed| allowed.as_str().eq_ignore_ascii_case(name))
+            })
+            .collect::<Vec<_>>();
+        if !rejected_headers.is_empty() {
+            return Some(format!(
+                "headers not allowed: {}",
+                rejected_headers.join(", ")
+            ));
+        }
+
+        None
+    }
+}
+
+/// 🚫 Middleware logging preflight requests the CORS policy rejects
+///
+/// Must wrap the CORS layer, which answers preflights without calling inner
+/// services.
+pub async fn log_rejected_preflights(
+    State(policy): State<CorsPolicy>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let is_preflight = request.method() == Method::OPTIONS
+        && request
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+    if is_preflight {
+        if let Some(origin) = request
+            .headers()
+            .get(header::ORIGIN)
+            .and_then(|value| value.to_str().ok())
+        {
+            if let Some(reason) = policy.preflight_rejection(origin, &request) {
+                tracing::warn!(
+                    origin,
+                    path = %request.uri().path(),
+                    reason,
+                    "Rejected CORS preflight"
+                );
+            }
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Parse every entry of a list setting, recording failures under `key`
+fn parse_all<T>(values: &[String], key: &str, errors: &mut Vec<String>) -> Vec<T>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    values
+        .iter()
+        .filter_map(|value| match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                errors.push(format!("{} entry {:?} is invalid: {}", key, value, e));
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(value: &str) -> OriginPattern {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_exact_origin() {
+        let allowed = pattern("https://app.example.com");
+        assert!(allowed.matches("https://app.example.com"));
+        assert!(allowed.matches("HTTPS://APP.EXAMPLE.COM"));
+        assert!(!allowed.matches("http://app.example.com"));
+        assert!(!allowed.matches("https://app.example.com:8443"));
+        assert!(!allowed.matches("https://app.example.com.evil.io"));
+    }
+
+    #[test]
+    fn test_wildcard_subdomain() {
+        let allowed = pattern("https://*.example.com");
+        assert!(allowed.matches("https://app.example.com"));
+        assert!(allowed.matches("https://a.b.example.com"));
+        assert!(!allowed.matches("https://example.com"));
+        assert!(!allowed.matches("https://evilexample.com"));
+        assert!(!allowed.matches("https://example.com.evil.io"));
+        assert!(!allowed.matches("http://app.example.com"));
+        assert!(!allowed.matches("https://app.example.com:8443"));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!("example.com".parse::<OriginPattern>().is_err());
+        assert!("https://".parse::<OriginPattern>().is_err());
+        assert!("https://app.example.com/".parse::<OriginPattern>().is_err());
+        assert!("https://app.*.example.com"
+            .parse::<OriginPattern>()
+            .is_err());
+        assert!("https://*example.com".parse::<OriginPattern>().is_err());
+        assert!("https://*.".parse::<OriginPattern>().is_err());
+    }
+
+    #[test]
+    fn test_credentials_with_any_origin_rejected() {
+        let config = CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+            allow_credentials: true,
+            ..CorsConfig::default()
+        };
+        assert!(CorsPolicy::from_config(&config).is_err());
+    }
+}
@@ -3,13 +3,36 @@
 //! Comprehensive error types with GraphQL integration
 
 use async_graphql::{ErrorExtensions, Result as GraphQLResult};
+use serde::Serialize;
 use thiserror::Error;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
 /// Application result type
 pub type AppResult<T> = Result<T, AppError>;
 
+/// 🏷️ A single invalid input field, reported in `extensions.fieldErrors`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    /// GraphQL field name (camelCase), with a `parent.child` or `list[0]` path when nested
+    pub field: String,
+    /// Machine-readable rule that failed (e.g. `LENGTH`, `EMAIL`, `BLANK`)
+    pub code: String,
+    /// Human-readable explanation
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
 /// Main error types for the application
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum AppError {
     #[error("Database error: {message}")]
     DatabaseError { message: String },
@@ -20,6 +43,9 @@ pub enum AppError {
     #[error("Unauthorized access")]
     Unauthorized,
 
+    #[error("Insufficient permissions")]
+    Forbidden,
+
     #[error("Invalid credentials")]
     InvalidCredentials,
 
@@ -29,12 +55,18 @@ pub enum AppError {
     #[error("User not found")]
     UserNotFound,
 
+    #[error("Account is deactivated")]
+    AccountDeactivated,
+
     #[error("Invalid UUID: {uuid}")]
     InvalidUuid { uuid: String },
 
     #[error("Validation error: {message}")]
     ValidationError { message: String },
 
+    #[error("Invalid input: {}", summarize_field_errors(.errors))]
+    InvalidInput { errors: Vec<FieldError> },
+
     #[error("Invalid content: {message}")]
     InvalidContent { message: String },
 
@@ -50,44 +82,83 @@ pub enum AppError {
     #[error("Configuration error: {message}")]
     ConfigError { message: String },
 
+    #[error("Search index error: {message}")]
+    SearchIndexError { message: String },
+
+    #[error("Mail error: {message}")]
+    MailError { message: String },
+
     #[error("Internal server error")]
     InternalServerError,
 }
 
+impl AppError {
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::DatabaseError { .. } => "DATABASE_ERROR",
+            AppError::AuthenticationFailed => "AUTHENTICATION_FAILED",
+            AppError::Unauthorized => "UNAUTHORIZED",
+            AppError::Forbidden => "FORBIDDEN",
+            AppError::InvalidCredentials => "INVALID_CREDENTIALS",
+            AppError::EmailAlreadyExists => "EMAIL_ALREADY_EXISTS",
+            AppError::UserNotFound => "USER_NOT_FOUND",
+            AppError::AccountDeactivated => "ACCOUNT_DEACTIVATED",
+            AppError::InvalidUuid { .. } => "INVALID_UUID",
+            AppError::ValidationError { .. } | AppError::InvalidInput { .. } => "VALIDATION_ERROR",
+            AppError::InvalidContent { .. } => "INVALID_CONTENT",
+            AppError::InvalidTitle { .. } => "INVALID_TITLE",
+            AppError::JwtError { .. } => "JWT_ERROR",
+            AppError::AuthError { .. } => "AUTH_ERROR",
+            AppError::ConfigError { .. } => "CONFIG_ERROR",
+            AppError::SearchIndexError { .. } => "SEARCH_INDEX_ERROR",
+            AppError::MailError { .. } => "MAIL_ERROR",
+            AppError::InternalServerError => "INTERNAL_SERVER_ERROR",
+        }
+    }
+}
+
+impl AppError {
+    /// Message safe to show to API clients
+    ///
+    /// Server-side failures carry internal details (SQL errors, hashing
+    /// failures) that are logged but never sent to clients.
+    pub fn public_message(&self) -> String {
+        match self {
+            AppError::DatabaseError { .. } => "A database error occurred".to_string(),
+            AppError::MailError { .. } => "Email could not be sent".to_string(),
+            AppError::AuthError { .. }
+            | AppError::ConfigError { .. }
+            | AppError::SearchIndexError { .. }
+            | AppError::InternalServerError => "Internal server error".to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
 impl ErrorExtensions for AppError {
     fn extend(&self) -> async_graphql::Error {
-        let extensions = match self {
-            AppError::DatabaseError { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "DATABASE_ERROR")),
-            AppError::AuthenticationFailed => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "AUTHENTICATION_FAILED")),
-            AppError::Unauthorized => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "UNAUTHORIZED")),
-            AppError::InvalidCredentials => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "INVALID_CREDENTIALS")),
-            AppError::EmailAlreadyExists => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "EMAIL_ALREADY_EXISTS")),
-            AppError::UserNotFound => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "USER_NOT_FOUND")),
-            AppError::InvalidUuid { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "INVALID_UUID")),
-            AppError::ValidationError { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "VALIDATION_ERROR")),
-            AppError::InvalidContent { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "INVALID_CONTENT")),
-            AppError::InvalidTitle { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "INVALID_TITLE")),
-            AppError::JwtError { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "JWT_ERROR")),
-            AppError::AuthError { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "AUTH_ERROR")),
-            AppError::ConfigError { .. } => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "CONFIG_ERROR")),
-            AppError::InternalServerError => async_graphql::Error::new(format!("{}", self))
-                .extend_with(|_, e| e.set("code", "INTERNAL_SERVER_ERROR")),
-        };
+        // Keep the original error as source so the request log can record internal details
+        let code = self.code();
+        let mut error = async_graphql::Error::new_with_source(self.clone());
+        error.message = self.public_message();
+        error.extend_with(|_, e| {
+            e.set("code", code);
+            if let AppError::InvalidInput { errors } = self {
+                if let Ok(fields) = async_graphql::Value::from_json(
+                    serde_json::to_value(errors).unwrap_or_default(),
+                ) {
+                    e.set("fieldErrors", fields);
+                }
+            }
+        })
+    }
+}
 
-        extensions
+/// `?` on an [`AppError`] inside a resolver keeps its code and redaction
+impl From<AppError> for async_graphql::Error {
+    fn from(err: AppError) -> Self {
+        err.extend()
     }
 }
 
@@ -97,3 +168,131 @@ impl<T> From<AppError> for GraphQLResult<T> {
         Err(err.extend())
     }
 }
+
+/// Collect every `validator` violation, including nested inputs, as field errors
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut field_errors = Vec::new();
+        collect_field_errors(&errors, "", &mut field_errors);
+        // HashMap order is random; keep responses stable
+        field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+        AppError::InvalidInput {
+            errors: field_errors,
+        }
+    }
+}
+
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = format!("{}{}", prefix, snake_to_camel(field));
+        match kind {
+            ValidationErrorsKind::Field(violations) => {
+                out.extend(violations.iter().map(|violation| {
+                    FieldError {
+                        field: path.clone(),
+                        code: violation.code.to_uppercase(),
+                        message: violation
+                            .message
+                            .as_ref()
+                            .map(|message| message.to_string())
+                            .unwrap_or_else(|| format!("{} is invalid", path)),
+                    }
+                }));
+            }
+            ValidationErrorsKind::Struct(nested) => {
+                collect_field_errors(nested, &format!("{}.", path), out);
+            }
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_errors(nested, &format!("{}[{}].", path, index), out);
+                }
+            }
+        }
+    }
+}
+
+/// `full_name` -> `fullName`, matching the GraphQL field names
+fn snake_to_camel(name: &str) -> String {
+    let mut parts = name.split('_');
+    let mut camel = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+/// `email: Invalid email format; password: ...` for logs and `Display`
+fn summarize_field_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::Value;
+    use validator::ValidationError;
+
+    #[test]
+    fn test_internal_errors_are_redacted() {
+        let error = AppError::DatabaseError {
+            message: "relation \"notes\" does not exist".to_string(),
+        }
+        .extend();
+
+        assert_eq!(error.message, "A database error occurred");
+        let code = error.extensions.as_ref().and_then(|ext| ext.get("code"));
+        assert_eq!(code, Some(&Value::from("DATABASE_ERROR")));
+        // The details stay available to server-side logging
+        let source = error
+            .source
+            .as_ref()
+            .and_then(|s| s.downcast_ref::<AppError>());
+        assert!(source.unwrap().to_string().contains("does not exist"));
+
+        let internal = AppError::AuthError {
+            message: "bcrypt failed".to_string(),
+        };
+        assert_eq!(internal.public_message(), "Internal server error");
+        assert_eq!(
+            AppError::InvalidCredentials.public_message(),
+            "Invalid credentials"
+        );
+    }
+
+    #[test]
+    fn test_validation_errors_become_field_errors() {
+        let mut errors = ValidationErrors::new();
+        errors.add("full_name", ValidationError::new("length"));
+        errors.add(
+            "email",
+            ValidationError::new("email").with_message("Invalid email format".into()),
+        );
+
+        let AppError::InvalidInput { errors } = AppError::from(errors) else {
+            panic!("expected InvalidInput");
+        };
+        assert_eq!(
+            errors,
+            vec![
+                FieldError {
+                    field: "email".to_string(),
+                    code: "EMAIL".to_string(),
+                    message: "Invalid email format".to_string(),
+                },
+                FieldError {
+                    field: "fullName".to_string(),
+                    code: "LENGTH".to_string(),
+                    message: "fullName is invalid".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! # Health Checks
+//!
+//! Endpoints for container orchestrators:
+//!
+//! - `GET /healthz`: liveness, succeeds as long as the process can serve HTTP
+//! - `GET /readyz`: readiness, checks the database connection and migrations
+//!   and fails once graceful shutdown has started
+//!
+//! Readiness responds with `200` when every check passes and `503` otherwise,
+//! with a JSON body describing each dependency.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use serde::Serialize;
+
+use crate::database::Database;
+
+/// Maximum time a readiness probe waits for the database
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 🚦 Shared readiness flag, flipped off when the server starts shutting down
+#[derive(Clone, Default)]
+pub struct Readiness {
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl Readiness {
+    /// Create a readiness flag for a server that is accepting traffic
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the server as shutting down; readiness probes fail from now on
+    pub fn mark_shutting_down(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether graceful shutdown has started
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+/// Shared state for the health routes
+#[derive(Clone)]
+pub struct HealthState {
+    pub db: Database,
+    pub readiness: Readiness,
+}
+
+/// Status of a single dependency check
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckResult {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pending: Vec<i64>,
+}
+
+impl CheckResult {
+    fn ok() -> Self {
+        Self {
+            status: "ok",
+            latency_ms: None,
+            error: None,
+            pending: vec![],
+        }
+    }
+
+    fn failing(error: impl Into<String>) -> Self {
+        Self {
+            status: "failing",
+            latency_ms: None,
+            error: Some(error.into()),
+            pending: vec![],
+        }
+    }
+
+    fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+/// Readiness response body
+#[derive(Serialize)]
+struct ReadinessReport {
+    status: &'static str,
+    checks: ReadinessChecks,
+}
+
+#[derive(Serialize)]
+struct ReadinessChecks {
+    database: CheckResult,
+    migrations: CheckResult,
+    shutdown: CheckResult,
+}
+
+/// 💓 Liveness probe
+pub async fn healthz() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// 🚦 Readiness probe
+pub async fn readyz(State(state): State<HealthState>) -> impl IntoResponse {
+    let database = check_database(&state.db).await;
+    let migrations = check_migrations(&state.db).await;
+    let shutdown = if state.readiness.is_shutting_down() {
+        CheckResult::failing("server is shutting down")
+    } else {
+        CheckResult::ok()
+    };
+
+    let ready = database.is_ok() && migrations.is_ok() && shutdown.is_ok();
+    let report = ReadinessReport {
+        status: if ready { "ready" } else { "degraded" },
+        checks: ReadinessChecks {
+            database,
+            migrations,
+            shutdown,
+        },
+    };
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+/// Ping the database within [`DB_CHECK_TIMEOUT`]
+async fn check_database(db: &Database) -> CheckResult {
+    let start = Instant::now();
+
+    let mut result = match tokio::time::timeout(DB_CHECK_TIMEOUT, db.ping()).await {
+        Ok(Ok(())) => CheckResult::ok(),
+        Ok(Err(e)) => CheckResult::failing(e.to_string()),
+        Err(_) => CheckResult::failing("database ping timed out"),
+    };
+    result.latency_ms = Some(start.elapsed().as_millis());
+    result
+}
+
+/// Verify every embedded migration has been applied
+async fn check_migrations(db: &Database) -> CheckResult {
+    match tokio::time::timeout(DB_CHECK_TIMEOUT, db.pending_migrations()).await {
+        Ok(Ok(pending)) if pending.is_empty() => CheckResult::ok(),
+        Ok(Ok(pending)) => CheckResult {
+            pending,
+            ..CheckResult::failing("migrations pending")
+        },
+        Ok(Err(e)) => CheckResult::failing(e.to_string()),
+        Err(_) => CheckResult::failing("migration check timed out"),
+    }
+}
@@ -0,0 +1,112 @@
+//! # Background Jobs
+//!
+//! Periodic maintenance tasks spawned at startup. Every job stops when the
+//! [`ShutdownSignal`] fires, after finishing the tick it is working on.
+
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::database::Database;
+use crate::search_backend::{sync_purged_users, SharedSearchBackend};
+use crate::shutdown::ShutdownSignal;
+use crate::views::ViewTracker;
+
+/// How often scheduled account deletions are processed
+const ACCOUNT_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often expired audit log events are deleted
+const AUDIT_PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often buffered note views are written to the database
+const VIEW_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 🗑️ Periodically hard-delete accounts whose deletion grace period has ended,
+/// dropping their notes from the search index
+pub fn spawn_account_purge(
+    db: Database,
+    search_backend: SharedSearchBackend,
+    shutdown: ShutdownSignal,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ACCOUNT_PURGE_INTERVAL);
+        let stopped = shutdown.recv();
+        tokio::pin!(stopped);
+
+        loop {
+            tokio::select! {
+                _ = &mut stopped => break,
+                _ = interval.tick() => {}
+            }
+
+            match db.purge_deleted_accounts().await {
+                Ok(user_ids) if user_ids.is_empty() => {}
+                Ok(user_ids) => {
+                    tracing::info!(count = user_ids.len(), "Purged deleted accounts");
+                    sync_purged_users(search_backend.as_ref(), &user_ids).await;
+                }
+                Err(e) => tracing::error!(error = %e, "Account purge failed"),
+            }
+        }
+
+        tracing::debug!("Account purge job stopped");
+    })
+}
+
+/// 🧹 Periodically delete audit log events older than `retention_days`
+pub fn spawn_audit_prune(
+    db: Database,
+    retention_days: i64,
+    shutdown: ShutdownSignal,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(AUDIT_PRUNE_INTERVAL);
+        let stopped = shutdown.recv();
+        tokio::pin!(stopped);
+
+        loop {
+            tokio::select! {
+                _ = &mut stopped => break,
+                _ = interval.tick() => {}
+            }
+
+            match db.prune_audit_events(retention_days).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!(count, "Pruned audit events"),
+                Err(e) => tracing::error!(error = %e, "Audit event pruning failed"),
+            }
+        }
+
+        tracing::debug!("Audit prune job stopped");
+    })
+}
+
+/// 👀 Periodically write buffered note views, and once more on shutdown
+pub fn spawn_view_flush(
+    db: Database,
+    views: ViewTracker,
+    shutdown: ShutdownSignal,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(VIEW_FLUSH_INTERVAL);
+        let stopped = shutdown.recv();
+        tokio::pin!(stopped);
+
+        loop {
+            let stopping = tokio::select! {
+                _ = &mut stopped => true,
+                _ = interval.tick() => false,
+            };
+
+            match views.flush(&db).await {
+                Ok(0) => {}
+                Ok(count) => tracing::debug!(count, "Flushed note views"),
+                Err(e) => tracing::error!(error = %e, "Note view flush failed"),
+            }
+            if stopping {
+                break;
+            }
+        }
+
+        tracing::debug!("View flush job stopped");
+    })
+}
@@ -30,41 +30,83 @@
 //! ## Quick Start
 //!
 //! 1. Set up PostgreSQL database
-//! 2. Set environment variables (DATABASE_URL, JWT_SECRET)
+//! 2. Copy `config.example.toml` to `config.toml` (or set DATABASE_URL, JWT_SECRET)
 //! 3. Run `cargo run` to start the server
 //! 4. Visit http://127.0.0.1:8000 for the landing page
 //! 5. Use http://127.0.0.1:8000/graphiql for API testing
 //!
-//! ## Environment Variables
+//! `backend --print-schema` writes the schema SDL to stdout, and
+//! `backend --diff-schema old.graphql new.graphql` reports breaking changes
+//! between two SDL files (see the [`schema`] module).
+//! `backend --rebuild-search-index` re-indexes every note into the configured
+//! search backend and exits (see the [`search_backend`] module).
+//! `backend --promote-admin <email>` gives an existing account the `ADMIN`
+//! role and exits; use it to bootstrap the first administrator.
 //!
-//! - `DATABASE_URL`: PostgreSQL connection string (required)
-//! - `JWT_SECRET`: Secret key for JWT signing (optional, auto-generated)
-//! - `PORT`: Server port (optional, defaults to 8000)
+//! ## Configuration
+//!
+//! Settings are loaded from `config.toml` (or `CONFIG_FILE`) with environment
+//! variable overrides; see the [`config`] module for every key. `RUST_LOG`
+//! controls log filtering (defaults to `info`).
 
+mod admin;
+mod audit;
 mod auth;
+mod config;
+mod cors;
 mod database;
 mod errors;
+mod health;
+mod jobs;
+mod mailer;
+mod monitoring;
+mod persisted_queries;
+mod query_language;
 mod resolvers;
+mod schema;
+mod search;
+mod search_backend;
+mod shutdown;
+mod tantivy_search;
+mod telemetry;
+mod templates;
 mod types;
+mod views;
 mod web;
 
 use async_graphql::{EmptySubscription, Schema};
 use axum::{
-    extract::{Request, State},
+    extract::{DefaultBodyLimit, Request, State},
     http::HeaderMap,
     middleware::{self, Next},
     response::Response,
-    routing::{get, post},
+    routing::get,
     Router,
 };
+use std::future::IntoFuture;
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
+use tower::ServiceBuilder;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer},
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::Level;
 
 use auth::AuthService;
+use config::Config;
+use cors::{log_rejected_preflights, CorsPolicy};
 use database::{create_database_pool, Database};
+use health::{healthz, readyz, HealthState, Readiness};
+use mailer::Mailer;
+use monitoring::{
+    metrics_handler, track_http_metrics, GraphQLMetrics, MetricsState, OperationNames,
+};
+use persisted_queries::PersistedQueries;
 use resolvers::{MutationRoot, QueryRoot};
-use web::{graphiql, graphql_handler, landing_page, AppSchema};
+use shutdown::{termination_signal, Shutdown};
+use views::ViewTracker;
+use web::{graphiql, graphql_handler, landing_page, schema_sdl, AppSchema, GraphQLState};
 
 /// 🔐 JWT Authentication Middleware
 ///
@@ -106,6 +148,31 @@ async fn jwt_middleware(
     next.run(request).await
 }
 
+/// Re-index every note into the configured search backend, then exit
+const REBUILD_SEARCH_INDEX_FLAG: &str = "--rebuild-search-index";
+
+/// Give the account with the following email the ADMIN role, then exit
+const PROMOTE_ADMIN_FLAG: &str = "--promote-admin";
+
+/// Header carrying the request ID (generated when the client does not send one)
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 🪪 Build the per-request tracing span, tagged with the request ID
+fn make_request_span(request: &Request) -> tracing::Span {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %request.method(),
+        uri = %request.uri(),
+    )
+}
+
 /// 🚀 Application Entry Point
 ///
 /// Initializes and starts the Smart Notes GraphQL API server with:
@@ -116,73 +183,300 @@ async fn jwt_middleware(
 /// - CORS support for cross-origin requests
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Schema tooling (--print-schema, --diff-schema) needs no config or database
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(code) = schema::run_command(&args) {
+        std::process::exit(code);
+    }
+
+    // Email of the account to promote with --promote-admin
+    let promote_admin = match args.iter().position(|arg| arg == PROMOTE_ADMIN_FLAG) {
+        Some(position) => match args.get(position + 1) {
+            Some(email) => Some(email.clone()),
+            None => {
+                eprintln!("Usage: backend {} <email>", PROMOTE_ADMIN_FLAG);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+
     // Load environment variables from .env file if present
     dotenv::dotenv().ok();
 
-    println!("🗃️  Initializing database connection...");
+    // Load and validate configuration before anything else starts
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Structured logging (logging.format = json|pretty, filtered by RUST_LOG)
+    telemetry::init_tracing(config.logging.format);
+
+    if let Some(path) = Config::source_file() {
+        tracing::info!(path = %path.display(), "Loaded configuration file");
+    }
+    if config.auth.uses_default_jwt_secret() {
+        tracing::warn!("Using the public development JWT secret; never enable this in production");
+    }
+
+    tracing::info!("Initializing database connection");
 
-    // Create PostgreSQL connection pool with automatic retries
-    let pool = create_database_pool().await?;
+    // Create PostgreSQL connection pool
+    let pool = create_database_pool(&config.database).await?;
     let db = Database::new(pool);
 
-    println!("⚡ Running database migrations...");
+    tracing::info!("Running database migrations");
     // Apply any pending database migrations
     db.migrate().await?;
 
-    println!("✅ Database ready!");
+    tracing::info!("Database ready");
+
+    if let Some(email) = promote_admin {
+        match db.promote_to_admin(&email).await? {
+            Some(user_id) => {
+                tracing::info!(%user_id, email = %email, "Promoted user to ADMIN");
+                return Ok(());
+            }
+            None => {
+                eprintln!("❌ No account with email {}", email);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let search_backend = search_backend::open_backend(&config.search, db.clone()).await?;
+    tracing::info!(backend = search_backend.name(), "Search backend ready");
+
+    if args.iter().any(|arg| arg == REBUILD_SEARCH_INDEX_FLAG) {
+        let count = search_backend.rebuild().await?;
+        tracing::info!(
+            count,
+            backend = search_backend.name(),
+            "Rebuilt search index"
+        );
+        return Ok(());
+    }
+
+    // Coordinates graceful shutdown of servers and background jobs
+    let shutdown = Shutdown::new();
+
+    // Start background maintenance jobs
+    let view_tracker = ViewTracker::new();
+    let background_jobs = vec![
+        jobs::spawn_account_purge(db.clone(), search_backend.clone(), shutdown.subscribe()),
+        jobs::spawn_audit_prune(
+            db.clone(),
+            config.accounts.audit_retention_days,
+            shutdown.subscribe(),
+        ),
+        jobs::spawn_view_flush(db.clone(), view_tracker.clone(), shutdown.subscribe()),
+    ];
+
+    // Prometheus recorder backing the /metrics route
+    let metrics_handle = monitoring::install_recorder()?;
 
     // Initialize JWT authentication service
-    let auth_service = AuthService::new();
+    let auth_service = AuthService::new(&config.auth);
+
+    // Persisted query cache and optional allowlist manifest
+    let persisted_queries = PersistedQueries::from_config(&config.persisted_queries)?;
+    if config.persisted_queries.manifest_file.is_some() {
+        tracing::info!(
+            operations = persisted_queries.manifest_len(),
+            allowlist_only = config.persisted_queries.allowlist_only,
+            "Loaded persisted query manifest"
+        );
+    }
+
+    // Operation names used as metric labels: listed ones, then the first
+    // metrics.max_operation_names others
+    let mut listed_operations = persisted_queries.manifest_operation_names();
+    listed_operations.extend(config.metrics.operations.iter().cloned());
+    let operation_names =
+        OperationNames::new(listed_operations, config.metrics.max_operation_names);
+
+    // Outgoing email for account verification
+    let mailer = Mailer::from_config(&config.mail)?;
+    if mailer.is_enabled() {
+        tracing::info!(transport = mailer.transport_name(), "Mail transport ready");
+    } else {
+        tracing::warn!("mail.transport is disabled; changeEmail will be rejected");
+    }
 
     // Build GraphQL schema with query/mutation resolvers and shared state
     let schema: AppSchema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
         .data(db.clone()) // Database access for resolvers
         .data(auth_service.clone()) // Auth service for login/register
+        .data(mailer) // Verification emails
+        .data(config.accounts.clone()) // Account lifecycle settings
+        .data(search_backend) // Backend for searchNotes
+        .data(view_tracker) // Buffered note views
+        .extension(GraphQLMetrics::new(operation_names)) // Per-operation metrics
+        .limit_depth(config.limits.max_query_depth)
+        .limit_complexity(config.limits.max_query_complexity)
         .finish();
 
+    // Serve /metrics on a separate admin port when server.metrics_port is set
+    let metrics_addr = config.server.metrics_addr();
+    let metrics_router = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .with_state(MetricsState {
+            handle: metrics_handle,
+            db: db.clone(),
+        });
+
+    let cors_policy =
+        CorsPolicy::from_config(&config.cors).expect("CORS settings are validated on load");
+
     // Build application routes with JWT middleware
     let app = Router::new()
         .route("/", get(landing_page)) // Beautiful landing page
         .route("/graphiql", get(graphiql)) // Interactive GraphQL playground
-        .route("/graphql", post(graphql_handler)) // GraphQL API endpoint
+        .route("/graphql", get(graphql_handler).post(graphql_handler)) // GraphQL API endpoint
+        .route("/schema.graphql", get(schema_sdl)) // Schema SDL (server.expose_schema)
         .layer(middleware::from_fn_with_state(
             // JWT authentication middleware
-            (auth_service, db),
+            (auth_service, db.clone()),
             jwt_middleware,
         ))
-        .layer(CorsLayer::permissive()) // CORS support
-        .with_state(schema); // GraphQL schema state
+        .layer(middleware::from_fn(track_http_metrics)) // HTTP metrics
+        .layer(cors_policy.layer()) // CORS from the [cors] settings
+        .layer(middleware::from_fn_with_state(
+            // Runs before the CORS layer answers preflights
+            cors_policy.clone(),
+            log_rejected_preflights,
+        ))
+        .layer(DefaultBodyLimit::max(config.limits.max_request_body_bytes))
+        .layer(
+            // Request IDs: accept or generate `x-request-id`, trace with it, echo it back
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::new(
+                    REQUEST_ID_HEADER.parse().expect("valid header name"),
+                    MakeRequestUuid,
+                ))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(make_request_span)
+                        .on_response(DefaultOnResponse::new().level(Level::INFO)),
+                )
+                .layer(PropagateRequestIdLayer::new(
+                    REQUEST_ID_HEADER.parse().expect("valid header name"),
+                )),
+        )
+        .with_state(GraphQLState {
+            schema,
+            persisted_queries,
+            max_batch_size: config.limits.max_batch_size,
+            trusted_proxies: config.server.trusted_proxies.clone(),
+            expose_schema: config.expose_schema(),
+        });
 
-    // Configure server port from environment or default to 8000
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "8000".to_string())
-        .parse::<u16>()
-        .unwrap_or(8000);
+    // Liveness/readiness probes live outside the JWT and GraphQL stack
+    let readiness = Readiness::new();
+    let health_router = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(HealthState {
+            db: db.clone(),
+            readiness: readiness.clone(),
+        });
+    let app = app.merge(health_router);
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    // The main port only serves /metrics when explicitly enabled
+    let app = if metrics_addr.is_none() && config.server.metrics_on_main_port {
+        app.merge(metrics_router.clone())
+    } else {
+        app
+    };
 
-    // Print server information and feature summary
-    println!(
-        "🚀 GraphQL server with JWT Authentication ready at http://127.0.0.1:{}",
-        port
-    );
-    println!("🌟 Beautiful landing page at http://127.0.0.1:{}", port);
-    println!(
-        "🎮 Interactive GraphiQL at http://127.0.0.1:{}/graphiql",
-        port
+    let addr = config.server.addr();
+
+    tracing::info!(
+        %addr,
+        graphiql = %format!("http://{}/graphiql", addr),
+        graphql = %format!("http://{}/graphql", addr),
+        "Smart Notes GraphQL server starting"
     );
-    println!("📡 GraphQL endpoint at http://127.0.0.1:{}/graphql", port);
-    println!("🐳 Database: Docker PostgreSQL (smart_notes) - Port 5433");
-    println!("🎯 Smart auto-title generation: ENABLED");
-    println!("🔍 Full-text search: ENABLED");
-    println!("🔐 Authentication: JWT-based with bcrypt password hashing");
-    println!("✨ JWT Middleware: OPERATIONAL!");
 
-    println!("🔧 Starting server on port {}", port);
+    if let Some(metrics_addr) = metrics_addr {
+        let metrics_listener = TcpListener::bind(&metrics_addr).await?;
+        tracing::info!(%metrics_addr, "Metrics endpoint listening on admin port");
+        let metrics_shutdown = shutdown.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(metrics_listener, metrics_router)
+                .with_graceful_shutdown(metrics_shutdown.recv())
+                .await
+            {
+                tracing::error!(error = %e, "Metrics server failed");
+            }
+        });
+    }
+
+    // Drain timeout for in-flight requests once shutdown starts
+    let drain_timeout = config.server.shutdown_drain_timeout();
+
+    // On SIGINT/SIGTERM: fail readiness first, and only start draining once
+    // load balancers had time to notice and stop sending new requests
+    {
+        let shutdown = shutdown.clone();
+        let pre_drain_delay = config.server.shutdown_pre_drain_delay();
+        tokio::spawn(async move {
+            termination_signal().await;
+            readiness.mark_shutting_down();
+            tracing::info!(
+                ?pre_drain_delay,
+                "Readiness failing, waiting before draining"
+            );
+            tokio::time::sleep(pre_drain_delay).await;
+            shutdown.trigger();
+        });
+    }
 
     // Start the HTTP server with graceful shutdown support
     let listener = TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    let mut server = tokio::spawn(
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown.subscribe().recv())
+        .into_future(),
+    );
+
+    // Wait for the server to exit, abandoning in-flight requests after the drain timeout
+    let drain_deadline = async {
+        shutdown.subscribe().recv().await;
+        tracing::info!(?drain_timeout, "Draining in-flight requests");
+        tokio::time::sleep(drain_timeout).await;
+    };
+    tokio::select! {
+        result = &mut server => result??,
+        _ = drain_deadline => {
+            tracing::warn!("Drain timeout exceeded, abandoning in-flight requests");
+            server.abort();
+        }
+    }
+
+    // Let background jobs finish their current tick (bounded by the same timeout)
+    shutdown.trigger();
+    let jobs_finished = async {
+        for job in background_jobs {
+            let _ = job.await;
+        }
+    };
+    if tokio::time::timeout(drain_timeout, jobs_finished)
+        .await
+        .is_err()
+    {
+        tracing::warn!("Background jobs did not stop in time");
+    }
+
+    db.close().await;
+    tracing::info!("Shutdown complete");
 
     Ok(())
 }
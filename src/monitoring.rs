@@ -0,0 +1,326 @@
+//! # Prometheus Metrics
+//!
+//! Exposes application metrics in the Prometheus text format at `/metrics`,
+//! on `server.metrics_port` or, when `server.metrics_on_main_port` is set, on
+//! the main port. The route has no authentication of its own.
+//!
+//! ## Metrics
+//!
+//! - `http_requests_total` / `http_request_duration_seconds`: by method, route and status
+//! - `graphql_operations_total` / `graphql_operation_duration_seconds`: by operation
+//!   name, operation type and outcome. Operation names come from clients, so
+//!   the label set is bounded: names in the persisted query manifest or
+//!   `metrics.operations` are always used, other names are admitted until
+//!   `metrics.max_operation_names` distinct ones have been seen, and the rest
+//!   are reported as `other`.
+//! - `graphql_errors_total`: by error code (the `code` extension, one per [`AppError`](crate::errors::AppError) variant)
+//! - `db_pool_connections`: SQLx pool size, idle and maximum connections
+//! - `auth_logins_total`: login attempts by result
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+};
+use async_graphql::parser::types::{ExecutableDocument, OperationType};
+use async_graphql::{Response, ServerResult, Value, Variables};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response as HttpResponse},
+};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::database::Database;
+
+/// Latency histogram buckets in seconds
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// 📈 Install the global Prometheus recorder
+pub fn install_recorder() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("duration_seconds".to_string()),
+            LATENCY_BUCKETS,
+        )?
+        .install_recorder()
+}
+
+/// Shared state for the `/metrics` route
+#[derive(Clone)]
+pub struct MetricsState {
+    pub handle: PrometheusHandle,
+    pub db: Database,
+}
+
+/// 📈 Render all metrics in the Prometheus text format
+pub async fn metrics_handler(State(state): State<MetricsState>) -> impl IntoResponse {
+    // Pool statistics are sampled at scrape time
+    let pool = state.db.pool_stats();
+    metrics::gauge!("db_pool_connections", "state" => "total").set(pool.size as f64);
+    metrics::gauge!("db_pool_connections", "state" => "idle").set(pool.idle as f64);
+    metrics::gauge!("db_pool_connections", "state" => "max").set(pool.max as f64);
+
+    state.handle.run_upkeep();
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.handle.render(),
+    )
+}
+
+/// 🌐 HTTP middleware recording request counts and latency per route
+pub async fn track_http_metrics(request: Request, next: Next) -> HttpResponse {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    // Use the route template rather than the raw path to keep label cardinality bounded
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    let labels = [("method", method), ("route", route), ("status", status)];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels)
+        .record(start.elapsed().as_secs_f64());
+
+    response
+}
+
+/// 🔑 Count a login attempt
+pub fn record_login(success: bool) {
+    let result = if success { "success" } else { "failure" };
+    metrics::counter!("auth_logins_total", "result" => result).increment(1);
+}
+
+/// Label for operations whose name is not a known one
+const OTHER_OPERATION: &str = "other";
+
+/// 🏷️ Bounded set of operation names used as metric labels
+pub struct OperationNames {
+    /// Names always reported as themselves (manifest and `metrics.operations`)
+    listed: HashSet<String>,
+    /// Other names admitted first-come, first-served
+    admitted: Mutex<HashSet<String>>,
+    /// Most names admitted on top of the listed ones
+    max_admitted: usize,
+}
+
+impl OperationNames {
+    pub fn new(listed: HashSet<String>, max_admitted: usize) -> Self {
+        Self {
+            listed,
+            admitted: Mutex::default(),
+            max_admitted,
+        }
+    }
+
+    /// Label for an operation called `name`
+    fn label(&self, name: &str) -> String {
+        if self.listed.contains(name) {
+            return name.to_string();
+        }
+
+        let mut admitted = self.admitted.lock().expect("metrics lock poisoned");
+        if admitted.contains(name) || admitted.len() < self.max_admitted {
+            admitted.insert(name.to_string());
+            name.to_string()
+        } else {
+            OTHER_OPERATION.to_string()
+        }
+    }
+}
+
+/// 🎯 async-graphql extension recording per-operation metrics
+pub struct GraphQLMetrics {
+    names: Arc<OperationNames>,
+}
+
+impl GraphQLMetrics {
+    pub fn new(names: OperationNames) -> Self {
+        Self {
+            names: Arc::new(names),
+        }
+    }
+}
+
+impl ExtensionFactory for GraphQLMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLMetricsExtension {
+            names: self.names.clone(),
+            state: Mutex::default(),
+        })
+    }
+}
+
+/// Per-request state of [`GraphQLMetrics`]
+struct GraphQLMetricsExtension {
+    names: Arc<OperationNames>,
+    state: Mutex<OperationState>,
+}
+
+/// Operations seen while parsing, and the one selected for execution
+#[derive(Default)]
+struct OperationState {
+    operations: Vec<(Option<String>, &'static str)>,
+    selected: Option<String>,
+}
+
+impl OperationState {
+    /// `(name, type)` labels of the executed operation
+    fn labels(&self, names: &OperationNames) -> (String, &'static str) {
+        let operation = match &self.selected {
+            Some(selected) => self
+                .operations
+                .iter()
+                .find(|(name, _)| name.as_deref() == Some(selected.as_str())),
+            None => self.operations.first(),
+        };
+
+        match operation {
+            Some((None, kind)) => ("anonymous".to_string(), kind),
+            Some((Some(name), kind)) => (names.label(name), kind),
+            // Requests that fail to parse never record an operation
+            None => ("unknown".to_string(), "unknown"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for GraphQLMetricsExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let start = Instant::now();
+        let response = next.run(ctx).await;
+
+        let (name, kind) = self
+            .state
+            .lock()
+            .expect("metrics lock poisoned")
+            .labels(&self.names);
+        let outcome = if response.errors.is_empty() {
+            "success"
+        } else {
+            "error"
+        };
+
+        let labels = [
+            ("operation", name),
+            ("type", kind.to_string()),
+            ("outcome", outcome.to_string()),
+        ];
+        metrics::counter!("graphql_operations_total", &labels).increment(1);
+        metrics::histogram!("graphql_operation_duration_seconds", &labels[..2])
+            .record(start.elapsed().as_secs_f64());
+
+        for error in &response.errors {
+            let code = match error
+                .extensions
+                .as_ref()
+                .and_then(|extensions| extensions.get("code"))
+            {
+                Some(Value::String(code)) => code.clone(),
+                _ => "GRAPHQL_ERROR".to_string(),
+            };
+            metrics::counter!("graphql_errors_total", "code" => code).increment(1);
+        }
+
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+
+        self.state.lock().expect("metrics lock poisoned").operations = document
+            .operations
+            .iter()
+            .map(|(name, op)| {
+                (
+                    name.map(|n| n.to_string()),
+                    operation_type_label(op.node.ty),
+                )
+            })
+            .collect();
+
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        self.state.lock().expect("metrics lock poisoned").selected =
+            operation_name.map(|name| name.to_string());
+
+        next.run(ctx, operation_name).await
+    }
+}
+
+/// Prometheus label for an operation type
+fn operation_type_label(ty: OperationType) -> &'static str {
+    match ty {
+        OperationType::Query => "query",
+        OperationType::Mutation => "mutation",
+        OperationType::Subscription => "subscription",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn executed(name: &str) -> OperationState {
+        OperationState {
+            operations: vec![(Some(name.to_string()), "query")],
+            selected: None,
+        }
+    }
+
+    #[test]
+    fn test_operation_names_without_a_manifest() {
+        let names = OperationNames::new(HashSet::new(), 2);
+
+        assert_eq!(
+            executed("MyNotes").labels(&names),
+            ("MyNotes".to_string(), "query")
+        );
+        assert_eq!(executed("Folders").labels(&names).0, "Folders");
+        assert_eq!(executed("Search").labels(&names).0, OTHER_OPERATION);
+        // Admitted names keep their label once the cap is reached
+        assert_eq!(executed("MyNotes").labels(&names).0, "MyNotes");
+    }
+
+    #[test]
+    fn test_listed_operation_names_do_not_count_against_the_cap() {
+        let names = OperationNames::new(HashSet::from(["Listed".to_string()]), 1);
+
+        assert_eq!(executed("First").labels(&names).0, "First");
+        assert_eq!(executed("Listed").labels(&names).0, "Listed");
+        assert_eq!(executed("Second").labels(&names).0, OTHER_OPERATION);
+
+        let anonymous = OperationState {
+            operations: vec![(None, "mutation")],
+            selected: None,
+        };
+        assert_eq!(
+            anonymous.labels(&names),
+            ("anonymous".to_string(), "mutation")
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! # Persisted Queries
+//!
+//! Lets clients send a SHA-256 hash instead of the full query document.
+//!
+//! ## Automatic Persisted Queries (APQ)
+//!
+//! Apollo-compatible protocol: the client sends only
+//! `extensions.persistedQuery = { version: 1, sha256Hash }`. If the hash is
+//! unknown the server answers with `PERSISTED_QUERY_NOT_FOUND` and the client
+//! retries with both the query and the hash, which registers the query in an
+//! in-memory LRU cache for subsequent requests.
+//!
+//! ## Manifest and Allowlist
+//!
+//! Operations can be pre-registered from an Apollo persisted query manifest
+//! (`generate-persisted-query-manifest` output), where every operation `id` is
+//! the SHA-256 of its `body`:
+//!
+//! ```json
+//! { "format": "apollo-persisted-query-manifest", "version": 1,
+//!   "operations": [{ "id": "<sha256>", "name": "MyNotes", "type": "query", "body": "query MyNotes { ... }" }] }
+//! ```
+//!
+//! With `allowlist_only` enabled, every other operation is rejected with
+//! `PERSISTED_QUERY_NOT_IN_LIST`, whether it arrives as a hash or as full text.
+//! This also blocks ad-hoc GraphiQL and introspection queries.
+
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::parser::parse_query;
+use lru::LruCache;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::PersistedQueriesConfig;
+use crate::errors::AppError;
+
+/// Only APQ protocol version in existence
+const APQ_VERSION: i64 = 1;
+
+/// Expected `format` of a manifest file
+const MANIFEST_FORMAT: &str = "apollo-persisted-query-manifest";
+
+/// 🚫 Reasons a request cannot be turned into a query document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistedQueryError {
+    /// Neither a query nor a persisted query hash was sent
+    MissingQuery,
+    /// `extensions.persistedQuery` is not `{ version, sha256Hash }`
+    Malformed,
+    UnsupportedVersion,
+    /// The sent query does not hash to the sent `sha256Hash`
+    HashMismatch,
+    /// Hash-only request while automatic persisted queries are disabled
+    NotSupported,
+    /// Hash-only request for a query that is not (or no longer) cached
+    NotFound,
+    /// Allowlist mode and the operation is not in the manifest
+    NotInAllowlist,
+}
+
+impl PersistedQueryError {
+    /// Stable error code for `extensions.code`
+    pub fn code(&self) -> &'static str {
+        match self {
+            PersistedQueryError::MissingQuery
+            | PersistedQueryError::Malformed
+            | PersistedQueryError::UnsupportedVersion
+            | PersistedQueryError::HashMismatch => "BAD_REQUEST",
+            PersistedQueryError::NotSupported => "PERSISTED_QUERY_NOT_SUPPORTED",
+            PersistedQueryError::NotFound => "PERSISTED_QUERY_NOT_FOUND",
+            PersistedQueryError::NotInAllowlist => "PERSISTED_QUERY_NOT_IN_LIST",
+        }
+    }
+
+    /// Error message (Apollo clients match on the `PersistedQuery*` ones)
+    pub fn message(&self) -> &'static str {
+        match self {
+            PersistedQueryError::MissingQuery => {
+                "Request must contain a query or a persisted query hash"
+            }
+            PersistedQueryError::Malformed => "Invalid extensions.persistedQuery",
+            PersistedQueryError::UnsupportedVersion => "Unsupported persisted query version",
+            PersistedQueryError::HashMismatch => "provided sha does not match query",
+            PersistedQueryError::NotSupported => "PersistedQueryNotSupported",
+            PersistedQueryError::NotFound => "PersistedQueryNotFound",
+            PersistedQueryError::NotInAllowlist => "Operation is not in the persisted query list",
+        }
+    }
+
+    /// GraphQL error object for the response `errors` list
+    pub fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "message": self.message(),
+            "extensions": { "code": self.code() },
+        })
+    }
+}
+
+/// `extensions.persistedQuery` of an incoming request
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedQueryExtension {
+    version: i64,
+    sha256_hash: String,
+}
+
+/// Apollo persisted query manifest file
+#[derive(Deserialize)]
+struct Manifest {
+    format: String,
+    version: i64,
+    operations: Vec<ManifestOperation>,
+}
+
+#[derive(Deserialize)]
+struct ManifestOperation {
+    id: String,
+    body: String,
+}
+
+/// 📌 Persisted query registry shared by all GraphQL requests (cheap to clone)
+#[derive(Clone)]
+pub struct PersistedQueries {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Automatically registered queries, `None` when APQ is disabled
+    cache: Option<Mutex<LruCache<String, Arc<str>>>>,
+    /// Pre-registered operations from the manifest, keyed by hash
+    manifest: HashMap<String, Arc<str>>,
+    allowlist_only: bool,
+}
+
+impl PersistedQueries {
+    /// Build the registry from the `[persisted_queries]` settings, loading the manifest
+    pub fn from_config(config: &PersistedQueriesConfig) -> Result<Self, AppError> {
+        let manifest = match &config.manifest_file {
+            Some(path) => load_manifest(path)?,
+            None => HashMap::new(),
+        };
+
+        let cache = NonZeroUsize::new(config.cache_size)
+            .filter(|_| config.automatic)
+            .map(|size| Mutex::new(LruCache::new(size)));
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                cache,
+                manifest,
+                allowlist_only: config.allowlist_only,
+            }),
+        })
+    }
+
+    /// Number of operations loaded from the manifest
+    pub fn manifest_len(&self) -> usize {
+        self.inner.manifest.len()
+    }
+
+    /// Names of the operations defined in the manifest
+    pub fn manifest_operation_names(&self) -> HashSet<String> {
+        self.inner
+            .manifest
+            .values()
+            .filter_map(|body| parse_query(body.as_ref()).ok())
+            .flat_map(|document| {
+                document
+                    .operations
+                    .iter()
+                    .filter_map(|(name, _)| name.map(|name| name.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// 🔎 Resolve the query document to execute for a request
+    ///
+    /// `query` and `extensions` are the raw request fields; a full query sent
+    /// together with its hash is registered for later hash-only requests.
+    pub fn resolve(
+        &self,
+        query: Option<String>,
+        extensions: Option<&serde_json::Value>,
+    ) -> Result<String, PersistedQueryError> {
+        let Some(persisted) = extensions.and_then(|ext| ext.get("persistedQuery")) else {
+            let query = query
+                .filter(|query| !query.trim().is_empty())
+                .ok_or(PersistedQueryError::MissingQuery)?;
+            if self.inner.allowlist_only && !self.inner.manifest.contains_key(&sha256_hex(&query)) {
+                return Err(PersistedQueryError::NotInAllowlist);
+            }
+            return Ok(query);
+        };
+
+        let persisted = PersistedQueryExtension::deserialize(persisted)
+            .map_err(|_| PersistedQueryError::Malformed)?;
+        if persisted.version != APQ_VERSION {
+            return Err(PersistedQueryError::UnsupportedVersion);
+        }
+        let hash = persisted.sha256_hash.to_ascii_lowercase();
+
+        // Registration: full query plus its hash
+        if let Some(query) = query {
+            if sha256_hex(&query) != hash {
+                return Err(PersistedQueryError::HashMismatch);
+            }
+            if self.inner.manifest.contains_key(&hash) {
+                return Ok(query);
+            }
+            if self.inner.allowlist_only {
+                return Err(PersistedQueryError::NotInAllowlist);
+            }
+            if let Some(cache) = &self.inner.cache {
+                cache
+                    .lock()
+                    .expect("persisted query cache lock poisoned")
+                    .put(hash, Arc::from(query.as_str()));
+            }
+            return Ok(query);
+        }
+
+        // Lookup: hash only
+        if let Some(query) = self.inner.manifest.get(&hash) {
+            return Ok(query.to_string());
+        }
+        if self.inner.allowlist_only {
+            return Err(PersistedQueryError::NotInAllowlist);
+        }
+        let cache = self
+            .inner
+            .cache
+            .as_ref()
+            .ok_or(PersistedQueryError::NotSupported)?;
+        cache
+            .lock()
+            .expect("persisted query cache lock poisoned")
+            .get(&hash)
+            .map(|query| query.to_string())
+            .ok_or(PersistedQueryError::NotFound)
+    }
+}
+
+/// Lowercase hex SHA-256 of a query document, as sent by Apollo clients
+fn sha256_hex(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
+/// Read a manifest file, checking that every operation id is the hash of its body
+fn load_manifest(path: &Path) -> Result<HashMap<String, Arc<str>>, AppError> {
+    let config_error = |message: String| AppError::ConfigError {
+        message: format!("persisted query manifest {}: {}", path.display(), message),
+    };
+
+    let contents = std::fs::read_to_string(path).map_err(|e| config_error(e.to_string()))?;
+    let manifest: Manifest =
+        serde_json::from_str(&contents).map_err(|e| config_error(e.to_string()))?;
+
+    if manifest.format != MANIFEST_FORMAT || manifest.version != 1 {
+        return Err(config_error(format!(
+            "expected format {:?} version 1",
+            MANIFEST_FORMAT
+        )));
+    }
+
+    manifest
+        .operations
+        .into_iter()
+        .map(|operation| {
+            let id = operation.id.to_ascii_lowercase();
+            if sha256_hex(&operation.body) != id {
+                return Err(config_error(format!(
+                    "operation id {} is not the SHA-256 of its body",
+                    operation.id
+                )));
+            }
+            Ok((id, Arc::from(operation.body)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY: &str = "{ me { id } }";
+
+    fn extensions(hash: &str) -> serde_json::Value {
+        serde_json::json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } })
+    }
+
+    fn registry(allowlist: &[&str], allowlist_only: bool) -> PersistedQueries {
+        PersistedQueries {
+            inner: Arc::new(Inner {
+                cache: Some(Mutex::new(LruCache::new(NonZeroUsize::new(2).unwrap()))),
+                manifest: allowlist
+                    .iter()
+                    .map(|query| (sha256_hex(query), Arc::from(*query)))
+                    .collect(),
+                allowlist_only,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_apq_register_then_lookup() {
+        let queries = registry(&[], false);
+        let ext = extensions(&sha256_hex(QUERY));
+
+        assert_eq!(
+            queries.resolve(None, Some(&ext)),
+            Err(PersistedQueryError::NotFound)
+        );
+        assert_eq!(
+            queries.resolve(Some(QUERY.to_string()), Some(&ext)),
+            Ok(QUERY.to_string())
+        );
+        assert_eq!(queries.resolve(None, Some(&ext)), Ok(QUERY.to_string()));
+    }
+
+    #[test]
+    fn test_apq_rejects_hash_mismatch() {
+        let queries = registry(&[], false);
+        let ext = extensions(&sha256_hex("{ other }"));
+
+        assert_eq!(
+            queries.resolve(Some(QUERY.to_string()), Some(&ext)),
+            Err(PersistedQueryError::HashMismatch)
+        );
+    }
+
+    #[test]
+    fn test_allowlist_only() {
+        let queries = registry(&[QUERY], true);
+
+        assert_eq!(
+            queries.resolve(None, Some(&extensions(&sha256_hex(QUERY)))),
+            Ok(QUERY.to_string())
+        );
+        assert_eq!(
+            queries.resolve(Some(QUERY.to_string()), None),
+            Ok(QUERY.to_string())
+        );
+        assert_eq!(
+            queries.resolve(Some("{ __schema { types { name } } }".to_string()), None),
+            Err(PersistedQueryError::NotInAllowlist)
+        );
+    }
+
+    #[test]
+    fn test_manifest_operation_names() {
+        let queries = registry(&[QUERY, "query MyNotes { notes { id } }"], false);
+
+        assert_eq!(
+            queries.manifest_operation_names(),
+            HashSet::from(["MyNotes".to_string()])
+        );
+    }
+}
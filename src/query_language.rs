@@ -0,0 +1,696 @@
+//! # Search Query Language
+//!
+//! Parses the `q` argument of `searchNotes` into a typed [`QueryAst`]:
+//!
+//! ```text
+//! tag:work folder:"Q3 plans" pinned:true before:2026-01-01 "exact phrase" -draft
+//! ```
+//!
+//! | Syntax | Meaning |
+//! |--------|---------|
+//! | `word`, `"exact phrase"`, `prefix*` | Full-text terms (all must match) |
+//! | `a or b` | Either text term |
+//! | `-term` | Exclude a word, phrase or prefix |
+//! | `tag:work` | Content contains the hashtag `#work` |
+//! | `folder:Ideas`, `folder:"Q3 plans"` | Note is in a folder with that name (case-insensitive); several `folder:` terms match any of them |
+//! | `pinned:true`, `pinned:false` | Pinned state |
+//! | `after:2025-06-01`, `before:2026-01-01` | Created on or after / before a date (UTC) or RFC3339 timestamp |
+//!
+//! `tag:` and `folder:` can be negated (`-tag:draft`). Anything that looks
+//! like `name:value` must use one of these fields; quote it (`"http://…"`) to
+//! search for it as text.
+
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::search::SearchFilters;
+
+/// Fields accepted before a `:`
+const FIELDS: &str = "tag:, folder:, pinned:, before: or after:";
+
+/// 🌳 A parsed query: text terms and field filters, in input order
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryAst {
+    pub clauses: Vec<Clause>,
+}
+
+/// One term of a query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clause {
+    /// Full-text term, excluded from results when `negated`
+    Text { term: TextTerm, negated: bool },
+    /// `or` between the text terms on either side
+    Or,
+    /// `tag:name` (stored lowercase, without `#`)
+    Tag { tag: String, negated: bool },
+    /// `folder:name` (folder name as written)
+    Folder { name: String, negated: bool },
+    /// `pinned:true|false`
+    Pinned(bool),
+    /// `before:date`, exclusive
+    Before(DateTime<Utc>),
+    /// `after:date`, inclusive
+    After(DateTime<Utc>),
+}
+
+/// Full-text part of a [`Clause::Text`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextTerm {
+    Word(String),
+    Phrase(String),
+    /// Words starting with the stem
+    Prefix(String),
+}
+
+/// ❌ Malformed query, with the 1-based column where the problem starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub column: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.column)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl QueryAst {
+    /// Parse a query, rejecting malformed syntax with a [`QueryError`]
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let clauses = Parser::new(input).clauses()?;
+        Ok(Self { clauses })
+    }
+
+    /// The text terms in the plain search syntax of [`crate::search::SearchQuery`]
+    pub fn text(&self) -> String {
+        self.clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                Clause::Text { term, negated } => {
+                    let sign = if *negated { "-" } else { "" };
+                    Some(match term {
+                        TextTerm::Word(word) => format!("{}{}", sign, word),
+                        TextTerm::Phrase(phrase) => format!("{}\"{}\"", sign, phrase),
+                        TextTerm::Prefix(stem) => format!("{}{}*", sign, stem),
+                    })
+                }
+                Clause::Or => Some("or".to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether the query has any field filter
+    pub fn has_filters(&self) -> bool {
+        self.clauses
+            .iter()
+            .any(|clause| !matches!(clause, Clause::Text { .. } | Clause::Or))
+    }
+
+    /// Add the field filters to `filters`; they take precedence over the same settings there
+    pub fn apply_filters(&self, filters: &mut SearchFilters) {
+        for clause in &self.clauses {
+            match clause {
+                Clause::Text { .. } | Clause::Or => {}
+                Clause::Tag { tag, negated } => {
+                    let tags = if *negated {
+                        &mut filters.excluded_tags
+                    } else {
+                        &mut filters.tags
+                    };
+                    tags.push(tag.clone());
+                }
+                Clause::Folder { name, negated } => {
+                    let names = if *negated {
+                        &mut filters.excluded_folder_names
+                    } else {
+                        &mut filters.folder_names
+                    };
+                    names.push(name.to_lowercase());
+                }
+                Clause::Pinned(pinned) => filters.is_pinned = Some(*pinned),
+                Clause::Before(at) => filters.created_before = Some(*at),
+                Clause::After(at) => filters.created_after = Some(*at),
+            }
+        }
+    }
+}
+
+/// Hand-written scanner over the query characters
+struct Parser<'a> {
+    input: &'a str,
+    chars: Vec<(usize, char)>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().collect(),
+            position: 0,
+        }
+    }
+
+    fn clauses(mut self) -> Result<Vec<Clause>, QueryError> {
+        let mut clauses = Vec::new();
+        let mut starts = Vec::new();
+        let mut pinned = None;
+
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                break;
+            }
+            let start = self.position;
+            let clause = self.clause()?;
+
+            if let Clause::Pinned(value) = clause {
+                if let Some(previous) = pinned.filter(|previous| *previous != value) {
+                    return Err(self.error_at(
+                        start,
+                        format!("Conflicting pinned:{} and pinned:{}", previous, value),
+                    ));
+                }
+                pinned = Some(value);
+            }
+            clauses.push(clause);
+            starts.push(start);
+        }
+
+        // `or` needs a text term on both sides
+        let is_text = |index: Option<usize>| {
+            matches!(
+                index.and_then(|index| clauses.get(index)),
+                Some(Clause::Text { .. })
+            )
+        };
+        for (index, clause) in clauses.iter().enumerate() {
+            if *clause == Clause::Or && !(is_text(index.checked_sub(1)) && is_text(Some(index + 1)))
+            {
+                return Err(self.error_at(starts[index], "'or' must be between two text terms"));
+            }
+        }
+
+        Ok(clauses)
+    }
+
+    fn clause(&mut self) -> Result<Clause, QueryError> {
+        let start = self.position;
+        let negated = self.eat('-');
+        match self.peek() {
+            None => return Err(self.error_at(start, "Expected a term after '-'")),
+            Some(ch) if ch.is_whitespace() => {
+                return Err(self.error_at(start, "Expected a term after '-'"))
+            }
+            Some('"') => {
+                let phrase = self.quoted()?;
+                return Ok(Clause::Text {
+                    term: TextTerm::Phrase(phrase),
+                    negated,
+                });
+            }
+            Some(_) => {}
+        }
+
+        let word_start = self.position;
+        let word = self.bare();
+        if self.peek() == Some(':') && is_field_name(word) {
+            self.position += 1;
+            return self.field(word, word_start, start, negated);
+        }
+        if self.peek() == Some('"') {
+            return Err(self.error_here("Unexpected '\"' inside a word"));
+        }
+        // Not a field (e.g. `10:30`): the rest of the token is text
+        let word = self.rest_of_token(word_start);
+
+        if word.eq_ignore_ascii_case("or") && !negated {
+            return Ok(Clause::Or);
+        }
+        let term = match word.strip_suffix('*') {
+            Some(stem) if !stem.is_empty() && stem.chars().all(char::is_alphanumeric) => {
+                TextTerm::Prefix(stem.to_string())
+            }
+            Some(_) if word.ends_with('*') && word.len() > 1 => {
+                return Err(self.error_at(
+                    word_start,
+                    "Prefix terms must be letters or digits followed by '*'",
+                ))
+            }
+            _ if word == "*" => {
+                return Err(self.error_at(word_start, "Expected a word before '*'"));
+            }
+            _ => TextTerm::Word(word.to_string()),
+        };
+        Ok(Clause::Text { term, negated })
+    }
+
+    fn field(
+        &mut self,
+        name: &str,
+        name_start: usize,
+        start: usize,
+        negated: bool,
+    ) -> Result<Clause, QueryError> {
+        let value_start = self.position;
+        let value = match self.peek() {
+            Some('"') => self.quoted()?,
+            _ => {
+                let value = self.bare();
+                let is_date = ["before", "after"]
+                    .iter()
+                    .any(|field| name.eq_ignore_ascii_case(field));
+                if is_date && self.peek() == Some(':') {
+                    // RFC3339 times contain colons
+                    self.rest_of_token(value_start).to_string()
+                } else if self.peek() == Some(':') || self.peek() == Some('"') {
+                    let value = self.rest_of_token(value_start);
+                    return Err(self.error_at(
+                        value_start,
+                        format!("Unexpected character in {}:{}", name, value),
+                    ));
+                } else {
+                    value.to_string()
+                }
+            }
+        };
+        if value.trim().is_empty() {
+            return Err(self.error_at(value_start, format!("Missing value after {}:", name)));
+        }
+
+        let field = name.to_ascii_lowercase();
+        match field.as_str() {
+            "tag" => Ok(Clause::Tag {
+                tag: parse_tag(&value).ok_or_else(|| {
+                    self.error_at(
+                        value_start,
+                        format!("Invalid tag '{}': use letters, digits, '_' and '-'", value),
+                    )
+                })?,
+                negated,
+            }),
+            "folder" => Ok(Clause::Folder {
+                name: value.trim().to_string(),
+                negated,
+            }),
+            "pinned" => {
+                let pinned = match value.to_ascii_lowercase().as_str() {
+                    "true" | "yes" => true,
+                    "false" | "no" => false,
+                    _ => {
+                        return Err(self.error_at(
+                            value_start,
+                            format!("Invalid pinned:{}; expected true or false", value),
+                        ))
+                    }
+                };
+                Ok(Clause::Pinned(pinned != negated))
+            }
+            "before" | "after" => {
+                if negated {
+                    return Err(self.error_at(start, format!("{}: cannot be negated", field)));
+                }
+                let at = parse_date(&value).ok_or_else(|| {
+                    self.error_at(
+                        value_start,
+                        format!(
+                            "Invalid date '{}' for {}:; expected YYYY-MM-DD or an RFC3339 timestamp",
+                            value, field
+                        ),
+                    )
+                })?;
+                Ok(if field == "before" {
+                    Clause::Before(at)
+                } else {
+                    Clause::After(at)
+                })
+            }
+            _ => Err(self.error_at(
+                name_start,
+                format!("Unknown field '{}:'; expected {}", name, FIELDS),
+            )),
+        }
+    }
+
+    /// Text between double quotes, after the opening quote at the current position
+    fn quoted(&mut self) -> Result<String, QueryError> {
+        let start = self.position;
+        self.position += 1;
+        let content_start = self.offset();
+        while let Some(ch) = self.peek() {
+            if ch == '"' {
+                let content = self.input[content_start..self.offset()].trim().to_string();
+                self.position += 1;
+                if content.is_empty() {
+                    return Err(self.error_at(start, "Empty quotes"));
+                }
+                if self.peek().is_some_and(|ch| !ch.is_whitespace()) {
+                    return Err(self.error_here("Expected a space after the closing quote"));
+                }
+                return Ok(content);
+            }
+            self.position += 1;
+        }
+        Err(self.error_at(start, "Unterminated quote"))
+    }
+
+    /// Characters up to whitespace, a quote or a colon
+    fn bare(&mut self) -> &'a str {
+        let start = self.offset();
+        while self
+            .peek()
+            .is_some_and(|ch| !ch.is_whitespace() && ch != '"' && ch != ':')
+        {
+            self.position += 1;
+        }
+        &self.input[start..self.offset()]
+    }
+
+    /// Characters from `start` up to whitespace (quotes are rejected)
+    fn rest_of_token(&mut self, start: usize) -> &'a str {
+        while self
+            .peek()
+            .is_some_and(|ch| !ch.is_whitespace() && ch != '"')
+        {
+            self.position += 1;
+        }
+        let begin = self.chars[start].0;
+        &self.input[begin..self.offset()]
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).map(|(_, ch)| *ch)
+    }
+
+    /// Byte offset of the current position
+    fn offset(&self) -> usize {
+        self.chars
+            .get(self.position)
+            .map_or(self.input.len(), |(offset, _)| *offset)
+    }
+
+    fn error_here(&self, message: impl Into<String>) -> QueryError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Into<String>) -> QueryError {
+        QueryError {
+            message: message.into(),
+            column: position + 1,
+        }
+    }
+}
+
+fn is_field_name(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|ch| ch.is_ascii_alphabetic())
+}
+
+/// Lowercase tag without a leading `#`, if it is a valid hashtag name
+fn parse_tag(value: &str) -> Option<String> {
+    let tag = value.strip_prefix('#').unwrap_or(value);
+    let valid = !tag.is_empty()
+        && tag
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '-');
+    valid.then(|| tag.to_lowercase())
+}
+
+/// `YYYY-MM-DD` (midnight UTC) or an RFC3339 timestamp
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).map(|at| at.and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn word(word: &str) -> Clause {
+        Clause::Text {
+            term: TextTerm::Word(word.to_string()),
+            negated: false,
+        }
+    }
+
+    fn midnight(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    fn error(input: &str) -> QueryError {
+        QueryAst::parse(input).expect_err(input)
+    }
+
+    #[test]
+    fn test_parses_the_full_example() {
+        let ast = QueryAst::parse(
+            r#"tag:work folder:"Q3 plans" pinned:true before:2026-01-01 "exact phrase" -draft"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ast.clauses,
+            vec![
+                Clause::Tag {
+                    tag: "work".to_string(),
+                    negated: false
+                },
+                Clause::Folder {
+                    name: "Q3 plans".to_string(),
+                    negated: false
+                },
+                Clause::Pinned(true),
+                Clause::Before(midnight(2026, 1, 1)),
+                Clause::Text {
+                    term: TextTerm::Phrase("exact phrase".to_string()),
+                    negated: false
+                },
+                Clause::Text {
+                    term: TextTerm::Word("draft".to_string()),
+                    negated: true
+                },
+            ]
+        );
+        assert_eq!(ast.text(), r#""exact phrase" -draft"#);
+        assert!(ast.has_filters());
+    }
+
+    #[test]
+    fn test_plain_text_terms() {
+        let ast = QueryAst::parse("rust  async*  or go -java* c++").unwrap();
+        assert_eq!(
+            ast.clauses,
+            vec![
+                word("rust"),
+                Clause::Text {
+                    term: TextTerm::Prefix("async".to_string()),
+                    negated: false
+                },
+                Clause::Or,
+                word("go"),
+                Clause::Text {
+                    term: TextTerm::Prefix("java".to_string()),
+                    negated: true
+                },
+                word("c++"),
+            ]
+        );
+        assert_eq!(ast.text(), "rust async* or go -java* c++");
+        assert!(!ast.has_filters());
+        assert_eq!(QueryAst::parse("   ").unwrap().clauses, vec![]);
+    }
+
+    #[test]
+    fn test_field_names_and_values_are_normalized() {
+        let ast = QueryAst::parse("TAG:#Work-Items Pinned:NO folder:Ideas -tag:draft").unwrap();
+        assert_eq!(
+            ast.clauses,
+            vec![
+                Clause::Tag {
+                    tag: "work-items".to_string(),
+                    negated: false
+                },
+                Clause::Pinned(false),
+                Clause::Folder {
+                    name: "Ideas".to_string(),
+                    negated: false
+                },
+                Clause::Tag {
+                    tag: "draft".to_string(),
+                    negated: true
+                },
+            ]
+        );
+        assert_eq!(
+            QueryAst::parse("-pinned:true").unwrap().clauses,
+            vec![Clause::Pinned(false)]
+        );
+    }
+
+    #[test]
+    fn test_dates() {
+        let ast = QueryAst::parse("after:2025-06-01 before:2025-07-01T12:30:00+02:00").unwrap();
+        assert_eq!(
+            ast.clauses,
+            vec![
+                Clause::After(midnight(2025, 6, 1)),
+                Clause::Before(Utc.with_ymd_and_hms(2025, 7, 1, 10, 30, 0).unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colons_outside_fields_are_text() {
+        let ast = QueryAst::parse(r#"meet 10:30 "http://example.com""#).unwrap();
+        assert_eq!(
+            ast.clauses,
+            vec![
+                word("meet"),
+                word("10:30"),
+                Clause::Text {
+                    term: TextTerm::Phrase("http://example.com".to_string()),
+                    negated: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filters_are_applied() {
+        let ast = QueryAst::parse(
+            r#"tag:a -tag:b folder:"Q3 Plans" -folder:Archive pinned:false after:2025-01-01 before:2026-01-01"#,
+        )
+        .unwrap();
+        let mut filters = SearchFilters {
+            is_pinned: Some(true),
+            ..Default::default()
+        };
+        ast.apply_filters(&mut filters);
+
+        assert_eq!(filters.tags, vec!["a"]);
+        assert_eq!(filters.excluded_tags, vec!["b"]);
+        assert_eq!(filters.folder_names, vec!["q3 plans"]);
+        assert_eq!(filters.excluded_folder_names, vec!["archive"]);
+        assert_eq!(filters.is_pinned, Some(false));
+        assert_eq!(filters.created_after, Some(midnight(2025, 1, 1)));
+        assert_eq!(filters.created_before, Some(midnight(2026, 1, 1)));
+    }
+
+    #[test]
+    fn test_unterminated_and_empty_quotes() {
+        assert_eq!(
+            error(r#"rust "borrow checker"#),
+            QueryError {
+                message: "Unterminated quote".to_string(),
+                column: 6
+            }
+        );
+        assert_eq!(error(r#"folder:"Q3"#).column, 8);
+        assert_eq!(error(r#"a "" b"#).message, "Empty quotes");
+        assert_eq!(
+            error(r#""phrase"word"#).message,
+            "Expected a space after the closing quote"
+        );
+        assert_eq!(error(r#"ab"cd""#).message, "Unexpected '\"' inside a word");
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        assert_eq!(
+            error("rust tga:work"),
+            QueryError {
+                message: format!("Unknown field 'tga:'; expected {}", FIELDS),
+                column: 6
+            }
+        );
+        assert_eq!(error("tag:").message, "Missing value after tag:");
+        assert_eq!(error("tag: work").message, "Missing value after tag:");
+        assert_eq!(
+            error("tag:a.b").message,
+            "Invalid tag 'a.b': use letters, digits, '_' and '-'"
+        );
+        assert_eq!(
+            error("pinned:maybe").message,
+            "Invalid pinned:maybe; expected true or false"
+        );
+        assert_eq!(
+            error("pinned:true pinned:false").message,
+            "Conflicting pinned:true and pinned:false"
+        );
+        assert_eq!(
+            error("folder:a:b").message,
+            "Unexpected character in folder:a:b"
+        );
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        let err = error("before:2026-13-01");
+        assert_eq!(err.column, 8);
+        assert_eq!(
+            err.message,
+            "Invalid date '2026-13-01' for before:; expected YYYY-MM-DD or an RFC3339 timestamp"
+        );
+        assert_eq!(
+            error("-after:2025-01-01").message,
+            "after: cannot be negated"
+        );
+    }
+
+    #[test]
+    fn test_invalid_operators() {
+        assert_eq!(error("rust -").message, "Expected a term after '-'");
+        assert_eq!(error("- rust").column, 1);
+        assert_eq!(error("*").message, "Expected a word before '*'");
+        assert_eq!(
+            error("c+*").message,
+            "Prefix terms must be letters or digits followed by '*'"
+        );
+        for (query, column) in [
+            ("or rust", 1),
+            ("rust or", 6),
+            ("rust or or go", 6),
+            ("tag:a or tag:b", 7),
+        ] {
+            assert_eq!(
+                error(query),
+                QueryError {
+                    message: "'or' must be between two text terms".to_string(),
+                    column
+                },
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_display_and_multibyte_columns() {
+        let err = error("café tag:");
+        assert_eq!(err.column, 10);
+        assert_eq!(err.to_string(), "Missing value after tag: (column 10)");
+    }
+}
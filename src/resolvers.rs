@@ -2,19 +2,34 @@
 //!
 //! This module implements resolvers with JWT-based authentication
 
-use async_graphql::{Context, EmptySubscription, Object, Result};
-use uuid; // Add this import
+use async_graphql::{ComplexObject, Context, EmptySubscription, Object, Result};
 use validator::Validate;
 
+use crate::admin::{AdminMutation, AdminQuery};
+use crate::audit::{
+    self, folder_snapshot, note_snapshot, AuditAction, AuditRecord, AuditTargetType,
+};
 use crate::auth::{
-    get_auth_context, require_auth, AuthResponse, AuthService, LoginInput, RegisterInput, User,
+    get_auth_context, require_auth, AuthResponse, AuthService, ChangeEmailInput,
+    ChangePasswordInput, LoginInput, RegisterInput, RoleGuard, UpdateProfileInput, User, UserRole,
 };
-use crate::database::Database;
-use crate::errors::{AppError, AppResult};
+use crate::config::AccountsConfig;
+use crate::database::{AuditEventFilters, Database, NoteFilters};
+use crate::errors::{AppError, FieldError};
+use crate::mailer::Mailer;
+use crate::monitoring;
+use crate::query_language::QueryAst;
+use crate::search::{SearchFilters, SearchQuery, DEFAULT_FUZZY_THRESHOLD};
+use crate::search_backend::{sync_note, SharedSearchBackend};
+use crate::templates::{self, TemplateValues};
 use crate::types::{
-    CreateFolderInput, Folder, MoveToFolderInput, Note, NoteInput, UpdateFolderInput,
-    UpdateNoteInput,
+    AnyFolder, AuditEvent, CreateFolderInput, CreateNoteTemplateInput, CreateSavedSearchInput,
+    DataExport, Folder, MoveToFolderInput, Note, NoteFilter, NoteInput, NoteOrderBy, NoteTemplate,
+    SearchInput, SearchResponse, SmartFolder, TemplateVariableInput, UpdateFolderInput,
+    UpdateNoteInput, UpdateNoteTemplateInput, UpdateSavedSearchInput, SEARCH_QUERY_MAX_LENGTH,
 };
+use crate::views::ViewTracker;
+use crate::web::ClientInfo;
 
 pub struct QueryRoot;
 pub struct MutationRoot;
@@ -38,52 +53,257 @@ impl QueryRoot {
         }
     }
 
-    /// 📚 Get user's notes (authenticated)
-    async fn notes(&self, ctx: &Context<'_>) -> Result<Vec<Note>> {
+    /// 📚 Get user's notes (authenticated), most recently updated first unless `orderBy` says otherwise
+    async fn notes(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<NoteFilter>,
+        order_by: Option<NoteOrderBy>,
+    ) -> Result<Vec<Note>> {
         let (user_id, _user) = require_auth(ctx)?;
+        let filter = filter.unwrap_or_default();
+        filter.validate().map_err(AppError::from)?;
         let db = ctx.data::<Database>()?;
-        let notes = db.get_user_notes(user_id).await?;
+
+        let filters = NoteFilters {
+            folder_id: parse_optional_uuid(filter.folder_id.as_deref())?,
+            include_subfolders: filter.include_subfolders.unwrap_or(false),
+            is_pinned: filter.is_pinned,
+            created_after: parse_optional_timestamp(
+                "createdAfter",
+                filter.created_after.as_deref(),
+            )?,
+            created_before: parse_optional_timestamp(
+                "createdBefore",
+                filter.created_before.as_deref(),
+            )?,
+            updated_after: parse_optional_timestamp(
+                "updatedAfter",
+                filter.updated_after.as_deref(),
+            )?,
+            updated_before: parse_optional_timestamp(
+                "updatedBefore",
+                filter.updated_before.as_deref(),
+            )?,
+            min_word_count: filter.min_word_count,
+            max_word_count: filter.max_word_count,
+            has_attachments: filter.has_attachments,
+        };
+        let notes = db
+            .get_user_notes(user_id, &filters, order_by.unwrap_or_default())
+            .await?;
         Ok(notes)
     }
 
-    /// 📚 Get all notes (admin/public access - remove in production)
-    async fn all_notes(&self, ctx: &Context<'_>) -> Result<Vec<Note>> {
-        let db = ctx.data::<Database>()?;
-        let notes = db.get_all_notes().await?;
-        Ok(notes)
+    /// 👑 Admin-only namespace (users, cross-tenant notes, system stats)
+    #[graphql(guard = "RoleGuard::new(UserRole::Admin)")]
+    async fn admin(&self) -> AdminQuery {
+        AdminQuery
     }
 
-    /// 🔍 Get note by ID (user-specific)
+    /// 🔍 Get note by ID (user-specific); counts as a view of the note
     async fn note(&self, ctx: &Context<'_>, id: String) -> Result<Option<Note>> {
-        let (_user_id, _user) = require_auth(ctx)?;
+        let (user_id, _user) = require_auth(ctx)?;
         let db = ctx.data::<Database>()?;
 
-        // First check if note exists and belongs to user
-        if let Some(note) = db.get_note_by_id(&id).await? {
-            // Verify note belongs to authenticated user (when we add user_id to notes)
-            // For now, just return the note
+        // Someone else's note is reported as not found and not counted
+        if let Some(note) = db.get_user_note(&id, user_id).await? {
+            if let Ok(note_id) = uuid::Uuid::parse_str(&note.id) {
+                ctx.data::<ViewTracker>()?.record(user_id, note_id);
+            }
             Ok(Some(note))
         } else {
             Ok(None)
         }
     }
 
-    /// 🔎 Search user's notes with full-text search (authenticated)
-    async fn search_notes(&self, ctx: &Context<'_>, query: String) -> Result<Vec<Note>> {
-        let (_user_id, _user) = require_auth(ctx)?;
+    /// 🕘 Notes the current user opened most recently
+    async fn recently_viewed_notes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Maximum number of notes (1-50, defaults to 10)")] limit: Option<i32>,
+    ) -> Result<Vec<Note>> {
+        let (user_id, _user) = require_auth(ctx)?;
         let db = ctx.data::<Database>()?;
 
-        // Search only user's notes (when implemented)
-        let notes = db.search_notes(&query).await?;
+        let limit = limit.unwrap_or(DEFAULT_RECENTLY_VIEWED_LIMIT);
+        if !(1..=50).contains(&limit) {
+            return Err(AppError::ValidationError {
+                message: "Limit must be between 1 and 50".to_string(),
+            }
+            .into());
+        }
+
+        let pending = ctx.data::<ViewTracker>()?.pending_for_user(user_id);
+        let notes = db
+            .get_recently_viewed_notes(user_id, &pending, limit.into())
+            .await?;
         Ok(notes)
     }
 
+    /// 🕵️ Activity on the current account (logins, changes, admin actions), newest first
+    async fn my_activity(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Only these actions")] actions: Option<Vec<AuditAction>>,
+        #[graphql(desc = "Maximum number of events (1-100, defaults to 20)")] limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<AuditEvent>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let limit = limit.unwrap_or(DEFAULT_ACTIVITY_LIMIT);
+        if !(1..=100).contains(&limit) {
+            return Err(AppError::ValidationError {
+                message: "Limit must be between 1 and 100".to_string(),
+            }
+            .into());
+        }
+
+        let filters = AuditEventFilters {
+            user_id: Some(user_id),
+            actions,
+            ..Default::default()
+        };
+        let events = db
+            .get_audit_events(&filters, limit.into(), offset.unwrap_or(0).max(0).into())
+            .await?;
+        Ok(events)
+    }
+
+    /// 🔎 Search the current user's notes, ranked by relevance with highlighted matches
+    ///
+    /// The query is either `q`, in the query language with field operators
+    /// (`tag:work folder:"Q3 plans" pinned:true before:2026-01-01 "exact phrase" -draft`),
+    /// or the plain text of `input.query`. The other `input` filters apply to both.
+    async fn search_notes(
+        &self,
+        ctx: &Context<'_>,
+        q: Option<String>,
+        input: Option<SearchInput>,
+    ) -> Result<SearchResponse> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let input = input.unwrap_or_default();
+        input.validate().map_err(AppError::from)?;
+        let db = ctx.data::<Database>()?;
+
+        let (raw_query, ast) = match (q, input.query.clone()) {
+            (Some(q), None) => {
+                let ast = parse_search_query("q", &q)?;
+                (q, Some(ast))
+            }
+            (None, Some(query)) => (query, None),
+            (Some(_), Some(_)) => {
+                return Err(AppError::ValidationError {
+                    message: "Use either `q` or `input.query`, not both".to_string(),
+                }
+                .into());
+            }
+            (None, None) => {
+                return Err(AppError::ValidationError {
+                    message: "Search query cannot be empty".to_string(),
+                }
+                .into());
+            }
+        };
+        let text = ast
+            .as_ref()
+            .map_or_else(|| raw_query.clone(), QueryAst::text);
+        let query = SearchQuery::parse(&text);
+        if query.is_empty() && !ast.as_ref().is_some_and(QueryAst::has_filters) {
+            return Err(AppError::ValidationError {
+                message: "Search query cannot be empty".to_string(),
+            }
+            .into());
+        }
+
+        let mut filters = SearchFilters {
+            folder_id: parse_optional_uuid(input.folder_id.as_deref())?,
+            include_subfolders: input.include_subfolders.unwrap_or(false),
+            is_pinned: input.is_pinned,
+            created_after: parse_optional_timestamp(
+                "createdAfter",
+                input.created_after.as_deref(),
+            )?,
+            created_before: parse_optional_timestamp(
+                "createdBefore",
+                input.created_before.as_deref(),
+            )?,
+            updated_after: parse_optional_timestamp(
+                "updatedAfter",
+                input.updated_after.as_deref(),
+            )?,
+            updated_before: parse_optional_timestamp(
+                "updatedBefore",
+                input.updated_before.as_deref(),
+            )?,
+            ..SearchFilters::default()
+        };
+        if let Some(ast) = &ast {
+            ast.apply_filters(&mut filters);
+        }
+        let fuzzy_threshold = input.fuzzy.unwrap_or(false).then(|| {
+            input
+                .similarity_threshold
+                .unwrap_or(DEFAULT_FUZZY_THRESHOLD)
+        });
+        let limit = input.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        let offset = input.offset.unwrap_or(0);
+
+        let backend = ctx.data::<SharedSearchBackend>()?;
+        let results = backend
+            .search(
+                user_id,
+                &text,
+                &query,
+                &filters,
+                fuzzy_threshold,
+                limit.into(),
+                offset.into(),
+            )
+            .await?;
+
+        // Suggest a correction on the first page when nothing matched exactly
+        let did_you_mean = if offset == 0
+            && !query.terms.is_empty()
+            && !results.iter().any(|result| result.exact_match)
+        {
+            let corrections = db.suggest_search_terms(user_id, &query.terms).await?;
+            SearchQuery::did_you_mean(&raw_query, &corrections)
+        } else {
+            None
+        };
+
+        Ok(SearchResponse {
+            results,
+            did_you_mean,
+        })
+    }
+
+    /// 🌐 Languages (text search configurations) available for notes and profiles
+    async fn search_languages(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        let db = ctx.data::<Database>()?;
+        Ok(db.search_languages().await?)
+    }
+
     /// 👤 Get current user profile
     async fn me(&self, ctx: &Context<'_>) -> Result<User> {
         let (_user_id, user) = require_auth(ctx)?;
         Ok(User::from(user.clone()))
     }
 
+    /// 📦 Export all personal data of the current user as JSON
+    async fn export_my_data(&self, ctx: &Context<'_>) -> Result<DataExport> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let data = db.export_user_data(user_id).await?;
+        Ok(DataExport {
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            data: async_graphql::Json(data),
+        })
+    }
+
     /// 📁 Get user's folders
     async fn folders(&self, ctx: &Context<'_>) -> Result<Vec<Folder>> {
         let (user_id, _user) = require_auth(ctx)?;
@@ -106,6 +326,56 @@ impl QueryRoot {
         Ok(folder)
     }
 
+    /// 🔖 Get user's smart folders (saved searches)
+    async fn smart_folders(&self, ctx: &Context<'_>) -> Result<Vec<SmartFolder>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        Ok(db.get_saved_searches(user_id).await?)
+    }
+
+    /// 🔖 Get smart folder by ID
+    async fn smart_folder(&self, ctx: &Context<'_>, id: String) -> Result<Option<SmartFolder>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let saved_search_id =
+            uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
+        Ok(db.get_saved_search(saved_search_id, user_id).await?)
+    }
+
+    /// 🧩 Note templates: the user's own and every shared one
+    async fn note_templates(&self, ctx: &Context<'_>) -> Result<Vec<NoteTemplate>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        Ok(db.get_note_templates(user_id).await?)
+    }
+
+    /// 🧩 Get one of the user's own or a shared note template
+    async fn note_template(&self, ctx: &Context<'_>, id: String) -> Result<Option<NoteTemplate>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let template_id =
+            uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
+        Ok(db.get_note_template(template_id, user_id).await?)
+    }
+
+    /// 🗂️ Get user's folders followed by their smart folders
+    async fn all_folders(&self, ctx: &Context<'_>) -> Result<Vec<AnyFolder>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let folders = db.get_user_folders(user_id).await?;
+        let smart_folders = db.get_saved_searches(user_id).await?;
+        Ok(folders
+            .into_iter()
+            .map(AnyFolder::Folder)
+            .chain(smart_folders.into_iter().map(AnyFolder::SmartFolder))
+            .collect())
+    }
+
     /// 📋 Get notes in a folder
     async fn notes_in_folder(&self, ctx: &Context<'_>, folder_id: String) -> Result<Vec<Note>> {
         let (user_id, _user) = require_auth(ctx)?;
@@ -132,32 +402,82 @@ impl QueryRoot {
 
 #[Object]
 impl MutationRoot {
+    /// 👑 Admin-only namespace (user status, password resets, token revocation)
+    #[graphql(guard = "RoleGuard::new(UserRole::Admin)")]
+    async fn admin(&self) -> AdminMutation {
+        AdminMutation
+    }
+
     /// 📝 Create note for authenticated user
     async fn create_note(&self, ctx: &Context<'_>, input: NoteInput) -> Result<Note> {
         let (user_id, _user) = require_auth(ctx)?;
+        create_user_note(ctx, user_id, input).await
+    }
 
-        // Validate input
-        validate_note_input(&input)?;
+    /// 🧩 Create a note from a template, filling in its placeholders
+    ///
+    /// `variables` must give a value for every entry of the template's
+    /// `prompts`. The note is titled like any other created note: the rendered
+    /// title template, or a title generated from the rendered content.
+    async fn create_note_from_template(
+        &self,
+        ctx: &Context<'_>,
+        template_id: String,
+        variables: Option<Vec<TemplateVariableInput>>,
+        folder_id: Option<String>,
+    ) -> Result<Note> {
+        let (user_id, user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
 
-        // Smart auto-title generation
-        let title = match input.title {
-            Some(title) if !title.trim().is_empty() => title,
-            _ => generate_smart_title(&input.content),
+        let template_uuid =
+            uuid::Uuid::parse_str(&template_id).map_err(|_| AppError::InvalidUuid {
+                uuid: template_id.clone(),
+            })?;
+        let template = db
+            .get_note_template(template_uuid, user_id)
+            .await?
+            .ok_or_else(|| AppError::InvalidInput {
+                errors: vec![FieldError::new(
+                    "templateId",
+                    "NOT_FOUND",
+                    "Template not found",
+                )],
+            })?;
+
+        let values = TemplateValues {
+            now: chrono::Utc::now(),
+            full_name: user.full_name.clone(),
+            email: user.email.clone(),
+            variables: variables
+                .unwrap_or_default()
+                .into_iter()
+                .map(|variable| (variable.name, variable.value))
+                .collect(),
+        };
+        let render = |text: &str| {
+            templates::render(text, &values).map_err(|name| AppError::InvalidInput {
+                errors: vec![FieldError::new(
+                    "variables",
+                    "MISSING_VARIABLE",
+                    format!("No value for {{{{{}}}}}", name),
+                )],
+            })
         };
 
-        let db = ctx.data::<Database>()?;
-
-        // Create note for authenticated user
-        let note = db
-            .create_note_for_user(user_id, &title, &input.content)
-            .await?;
-        Ok(note)
+        let input = NoteInput {
+            title: template.title_template.as_deref().map(render).transpose()?,
+            content: render(&template.content_template)?,
+            folder_id,
+            is_pinned: None,
+            language: None,
+        };
+        create_user_note(ctx, user_id, input).await
     }
 
     /// 📝 Create public note (legacy - for testing)
     async fn create_public_note(&self, ctx: &Context<'_>, input: NoteInput) -> Result<Note> {
         // Validate input
-        validate_note_input(&input)?;
+        input.validate().map_err(AppError::from)?;
 
         // Smart auto-title generation
         let title = match input.title {
@@ -167,6 +487,7 @@ impl MutationRoot {
 
         let db = ctx.data::<Database>()?;
         let note = db.create_note(&title, &input.content).await?;
+        sync_search_index(ctx, &note.id, false).await?;
         Ok(note)
     }
 
@@ -177,23 +498,68 @@ impl MutationRoot {
         id: String,
         input: UpdateNoteInput,
     ) -> Result<Option<Note>> {
-        let (_user_id, _user) = require_auth(ctx)?;
+        let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
         let db = ctx.data::<Database>()?;
 
-        // TODO: Verify note belongs to user before updating
+        // An empty language clears the override
+        let language = input
+            .language
+            .as_deref()
+            .map(|language| Some(language.trim()).filter(|language| !language.is_empty()));
+        if let Some(language) = language {
+            check_search_language(db, "language", language).await?;
+        }
+
+        // Someone else's note is reported as not found
+        let Some(before) = db.get_user_note(&id, user_id).await? else {
+            return Ok(None);
+        };
         let note = db
-            .update_note(&id, input.title.as_deref(), input.content.as_deref())
+            .update_note(
+                &id,
+                user_id,
+                input.title.as_deref(),
+                input.content.as_deref(),
+            )
             .await?;
+        let note = match (note, language) {
+            (Some(_), Some(language)) => db.set_note_language(&id, user_id, language).await?,
+            (note, _) => note,
+        };
+        if let Some(after) = &note {
+            sync_search_index(ctx, &id, false).await?;
+            audit::record(
+                ctx,
+                AuditRecord::new(AuditAction::NoteUpdated, user_id)
+                    .target(AuditTargetType::Note, &id)
+                    .changes(audit::diff(&note_snapshot(&before), &note_snapshot(after))),
+            )
+            .await;
+        }
         Ok(note)
     }
 
     /// 🗑️ Delete user's note
     async fn delete_note(&self, ctx: &Context<'_>, id: String) -> Result<bool> {
-        let (_user_id, _user) = require_auth(ctx)?;
+        let (user_id, _user) = require_auth(ctx)?;
         let db = ctx.data::<Database>()?;
 
-        // TODO: Verify note belongs to user before deleting
-        let deleted = db.delete_note(&id).await?;
+        // Someone else's note is reported as not found
+        let Some(before) = db.get_user_note(&id, user_id).await? else {
+            return Ok(false);
+        };
+        let deleted = db.delete_note(&id, user_id).await?;
+        if deleted {
+            sync_search_index(ctx, &id, true).await?;
+            audit::record(
+                ctx,
+                AuditRecord::new(AuditAction::NoteDeleted, user_id)
+                    .target(AuditTargetType::Note, &id)
+                    .changes(audit::diff(&note_snapshot(&before), &serde_json::json!({}))),
+            )
+            .await;
+        }
         Ok(deleted)
     }
 
@@ -205,46 +571,274 @@ impl MutationRoot {
         // Create user
         let user_row = db.create_user(&input, auth).await?;
         let user = User::from(user_row.clone());
+        audit::record(ctx, AuditRecord::new(AuditAction::Registered, user_row.id)).await;
 
         // Generate JWT token
-        let token = auth.generate_token(user_row.id, user_row.email)?;
+        let token = auth.generate_token(&user_row)?;
 
         Ok(AuthResponse { token, user })
     }
 
     /// 🔑 Login user
     async fn login(&self, ctx: &Context<'_>, input: LoginInput) -> Result<AuthResponse> {
-        input.validate().map_err(|e| AppError::ValidationError {
-            message: format!("Validation failed: {}", e),
-        })?;
+        input.validate().map_err(AppError::from)?;
 
         let db = ctx.data::<Database>()?;
         let auth = ctx.data::<AuthService>()?;
 
         // Get user by email
-        let user_row = db
-            .get_user_by_email(&input.email)
-            .await?
-            .ok_or(AppError::InvalidCredentials)?;
+        let Some(user_row) = db.get_user_by_email(&input.email).await? else {
+            monitoring::record_login(false);
+            let email = serde_json::json!({ "email": input.email.trim() });
+            audit::record(
+                ctx,
+                AuditRecord::anonymous(AuditAction::LoginFailed)
+                    .changes(audit::diff(&serde_json::json!({}), &email)),
+            )
+            .await;
+            return Err(AppError::InvalidCredentials.into());
+        };
 
         // Verify password
         let is_valid = auth.verify_password(&input.password, &user_row.password_hash)?;
+
+        // Record the attempt for the user's login history; a failure here
+        // must not lock users out
+        let client = ctx.data_opt::<ClientInfo>().cloned().unwrap_or_default();
+        if let Err(e) = db
+            .record_login(
+                user_row.id,
+                is_valid,
+                client.ip_address.as_deref(),
+                client.user_agent.as_deref(),
+            )
+            .await
+        {
+            tracing::warn!(error = %e, user_id = %user_row.id, "Failed to record login history");
+        }
+        monitoring::record_login(is_valid);
+
+        let action = if is_valid && user_row.is_active {
+            AuditAction::Login
+        } else {
+            AuditAction::LoginFailed
+        };
+        audit::record(ctx, AuditRecord::new(action, user_row.id)).await;
+
         if !is_valid {
             return Err(AppError::InvalidCredentials.into());
         }
 
-        let user = User::from(user_row.clone());
-        let token = auth.generate_token(user_row.id, user_row.email)?;
+        // Only reveal the account state to callers who know the password
+        if !user_row.is_active {
+            return Err(AppError::AccountDeactivated.into());
+        }
+
+        let token = auth.generate_token(&user_row)?;
+        let user = User::from(user_row);
 
         Ok(AuthResponse { token, user })
     }
 
+    /// 👤 Update the current user's profile
+    async fn update_profile(
+        &self,
+        ctx: &Context<'_>,
+        mut input: UpdateProfileInput,
+    ) -> Result<User> {
+        let (user_id, user) = require_auth(ctx)?;
+        // Validate what is stored: "  A  " is a one-character name
+        for value in [&mut input.full_name, &mut input.language]
+            .into_iter()
+            .flatten()
+        {
+            *value = value.trim().to_string();
+        }
+        input.validate().map_err(AppError::from)?;
+
+        if input.full_name.is_none() && input.language.is_none() {
+            return Ok(User::from(user.clone()));
+        }
+
+        let db = ctx.data::<Database>()?;
+        let language = input.language.as_deref();
+        check_search_language(db, "language", language).await?;
+
+        let full_name = input.full_name.as_deref();
+        let updated = db
+            .update_user_profile(user_id, full_name, language)
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+        Ok(User::from(updated))
+    }
+
+    /// 🔑 Change the current user's password
+    ///
+    /// All previously issued tokens are revoked; the response carries a fresh
+    /// token for the current session.
+    async fn change_password(
+        &self,
+        ctx: &Context<'_>,
+        input: ChangePasswordInput,
+    ) -> Result<AuthResponse> {
+        let (user_id, user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
+
+        let db = ctx.data::<Database>()?;
+        let auth = ctx.data::<AuthService>()?;
+
+        if !auth.verify_password(&input.current_password, &user.password_hash)? {
+            return Err(AppError::InvalidCredentials.into());
+        }
+
+        let password_hash = auth.hash_password(&input.new_password)?;
+        let updated = db
+            .update_user_password(user_id, &password_hash)
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+        audit::record(ctx, AuditRecord::new(AuditAction::PasswordChanged, user_id)).await;
+
+        let token = auth.generate_token(&updated)?;
+        Ok(AuthResponse {
+            token,
+            user: User::from(updated),
+        })
+    }
+
+    /// 📧 Request an email change; a verification token is sent to the new address
+    async fn change_email(&self, ctx: &Context<'_>, input: ChangeEmailInput) -> Result<bool> {
+        let (user_id, user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
+
+        let db = ctx.data::<Database>()?;
+        let auth = ctx.data::<AuthService>()?;
+        let mailer = ctx.data::<Mailer>()?;
+        let accounts = ctx.data::<AccountsConfig>()?;
+
+        if !auth.verify_password(&input.password, &user.password_hash)? {
+            return Err(AppError::InvalidCredentials.into());
+        }
+        // Without a transport the token could never reach the new address
+        if !mailer.is_enabled() {
+            return Err(AppError::MailError {
+                message: "no mail transport is configured".to_string(),
+            }
+            .into());
+        }
+
+        if db.get_user_by_email(&input.new_email).await?.is_some() {
+            return Err(AppError::EmailAlreadyExists.into());
+        }
+
+        let (token, token_hash) = auth.generate_opaque_token();
+        let expires_at =
+            chrono::Utc::now() + chrono::Duration::hours(accounts.email_change_ttl_hours);
+        db.create_email_change_request(user_id, &input.new_email, &token_hash, expires_at)
+            .await?;
+        mailer
+            .send_email_change_verification(input.new_email.trim(), &token)
+            .await?;
+        audit::record(
+            ctx,
+            AuditRecord::new(AuditAction::EmailChangeRequested, user_id).changes(audit::diff(
+                &serde_json::json!({ "email": user.email }),
+                &serde_json::json!({ "email": input.new_email.trim() }),
+            )),
+        )
+        .await;
+
+        Ok(true)
+    }
+
+    /// ✅ Confirm a pending email change with the emailed token
+    async fn confirm_email_change(&self, ctx: &Context<'_>, token: String) -> Result<AuthResponse> {
+        let db = ctx.data::<Database>()?;
+        let auth = ctx.data::<AuthService>()?;
+
+        let updated = db
+            .confirm_email_change(&AuthService::hash_opaque_token(&token))
+            .await?
+            .ok_or_else(|| AppError::ValidationError {
+                message: "Invalid or expired email verification token".to_string(),
+            })?;
+        audit::record(
+            ctx,
+            AuditRecord::new(AuditAction::EmailChanged, updated.id).changes(audit::diff(
+                &serde_json::json!({}),
+                &serde_json::json!({ "email": updated.email }),
+            )),
+        )
+        .await;
+
+        let token = auth.generate_token(&updated)?;
+        Ok(AuthResponse {
+            token,
+            user: User::from(updated),
+        })
+    }
+
+    /// 🗑️ Schedule deletion of the current account after a grace period
+    ///
+    /// Returns the user with `deletionScheduledAt` set. Logging in remains
+    /// possible until then so the request can be cancelled.
+    async fn request_account_deletion(&self, ctx: &Context<'_>, password: String) -> Result<User> {
+        let (user_id, user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+        let auth = ctx.data::<AuthService>()?;
+
+        if !auth.verify_password(&password, &user.password_hash)? {
+            return Err(AppError::InvalidCredentials.into());
+        }
+
+        let accounts = ctx.data::<AccountsConfig>()?;
+        let scheduled_at =
+            chrono::Utc::now() + chrono::Duration::days(accounts.deletion_grace_days);
+        let updated = db
+            .set_account_deletion(user_id, Some(scheduled_at))
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+        audit::record(
+            ctx,
+            AuditRecord::new(AuditAction::AccountDeletionRequested, user_id),
+        )
+        .await;
+        Ok(User::from(updated))
+    }
+
+    /// ↩️ Cancel a pending account deletion
+    async fn cancel_account_deletion(&self, ctx: &Context<'_>) -> Result<User> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let updated = db
+            .set_account_deletion(user_id, None)
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+        audit::record(
+            ctx,
+            AuditRecord::new(AuditAction::AccountDeletionCancelled, user_id),
+        )
+        .await;
+        Ok(User::from(updated))
+    }
+
     /// 📁 Create a new folder
     async fn create_folder(&self, ctx: &Context<'_>, input: CreateFolderInput) -> Result<Folder> {
         let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
         let db = ctx.data::<Database>()?;
 
         let folder = db.create_folder(user_id, &input).await?;
+        audit::record(
+            ctx,
+            AuditRecord::new(AuditAction::FolderCreated, user_id)
+                .target(AuditTargetType::Folder, &folder.id)
+                .changes(audit::diff(
+                    &serde_json::json!({}),
+                    &folder_snapshot(&folder),
+                )),
+        )
+        .await;
         Ok(folder)
     }
 
@@ -256,13 +850,27 @@ impl MutationRoot {
         input: UpdateFolderInput,
     ) -> Result<Option<Folder>> {
         let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
         let db = ctx.data::<Database>()?;
 
         // Parse string ID to UUID
         let folder_uuid =
             uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
 
+        let before = db.get_folder_by_id(folder_uuid, user_id).await?;
         let folder = db.update_folder(folder_uuid, user_id, &input).await?;
+        if let (Some(before), Some(after)) = (&before, &folder) {
+            audit::record(
+                ctx,
+                AuditRecord::new(AuditAction::FolderUpdated, user_id)
+                    .target(AuditTargetType::Folder, &id)
+                    .changes(audit::diff(
+                        &folder_snapshot(before),
+                        &folder_snapshot(after),
+                    )),
+            )
+            .await;
+        }
         Ok(folder)
     }
 
@@ -275,10 +883,128 @@ impl MutationRoot {
         let folder_uuid =
             uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
 
+        let before = db.get_folder_by_id(folder_uuid, user_id).await?;
+        // Subfolders go with the folder; their notes stay, without a folder
+        let scope = db.folder_scope(user_id, folder_uuid, true).await?;
+        let note_ids = db.note_ids_in_folders(user_id, &scope).await?;
         let deleted = db.delete_folder(folder_uuid, user_id, None).await?;
+        if deleted {
+            for note_id in &note_ids {
+                sync_search_index(ctx, &note_id.to_string(), false).await?;
+            }
+        }
+        if let (true, Some(before)) = (deleted, &before) {
+            audit::record(
+                ctx,
+                AuditRecord::new(AuditAction::FolderDeleted, user_id)
+                    .target(AuditTargetType::Folder, &id)
+                    .changes(audit::diff(
+                        &folder_snapshot(before),
+                        &serde_json::json!({}),
+                    )),
+            )
+            .await;
+        }
         Ok(deleted)
     }
 
+    /// 🔖 Save a search as a smart folder
+    async fn create_saved_search(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateSavedSearchInput,
+    ) -> Result<SmartFolder> {
+        let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
+        let db = ctx.data::<Database>()?;
+
+        parse_search_query("query", &input.query)?;
+        let folder_id = parse_optional_uuid(input.folder_id.as_deref())?;
+        check_folder_owner(db, user_id, folder_id).await?;
+
+        Ok(db.create_saved_search(user_id, &input, folder_id).await?)
+    }
+
+    /// 🔖 Update a saved search
+    async fn update_saved_search(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        input: UpdateSavedSearchInput,
+    ) -> Result<Option<SmartFolder>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
+        let db = ctx.data::<Database>()?;
+
+        let saved_search_id =
+            uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
+        if let Some(query) = &input.query {
+            parse_search_query("query", query)?;
+        }
+        // An empty folder ID clears the folder
+        let folder_id = match input.folder_id.as_deref() {
+            None => None,
+            Some("") => Some(None),
+            Some(id) => Some(parse_optional_uuid(Some(id))?),
+        };
+        check_folder_owner(db, user_id, folder_id.flatten()).await?;
+
+        Ok(db
+            .update_saved_search(saved_search_id, user_id, &input, folder_id)
+            .await?)
+    }
+
+    /// 🗑️ Delete a saved search
+    async fn delete_saved_search(&self, ctx: &Context<'_>, id: String) -> Result<bool> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let saved_search_id =
+            uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
+        Ok(db.delete_saved_search(saved_search_id, user_id).await?)
+    }
+
+    /// 🧩 Create a note template
+    async fn create_note_template(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateNoteTemplateInput,
+    ) -> Result<NoteTemplate> {
+        let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
+        let db = ctx.data::<Database>()?;
+
+        Ok(db.create_note_template(user_id, &input).await?)
+    }
+
+    /// 🧩 Update one of the user's own note templates
+    async fn update_note_template(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        input: UpdateNoteTemplateInput,
+    ) -> Result<Option<NoteTemplate>> {
+        let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
+        let db = ctx.data::<Database>()?;
+
+        let template_id =
+            uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
+        Ok(db
+            .update_note_template(template_id, user_id, &input)
+            .await?)
+    }
+
+    /// 🗑️ Delete one of the user's own note templates
+    async fn delete_note_template(&self, ctx: &Context<'_>, id: String) -> Result<bool> {
+        let (user_id, _user) = require_auth(ctx)?;
+        let db = ctx.data::<Database>()?;
+
+        let template_id =
+            uuid::Uuid::parse_str(&id).map_err(|_| AppError::InvalidUuid { uuid: id.clone() })?;
+        Ok(db.delete_note_template(template_id, user_id).await?)
+    }
+
     /// 📌 Toggle note pin status
     async fn toggle_note_pin(&self, ctx: &Context<'_>, note_id: String) -> Result<Note> {
         let (user_id, _user) = require_auth(ctx)?;
@@ -289,7 +1015,21 @@ impl MutationRoot {
             uuid: note_id.clone(),
         })?;
 
-        let note = db.toggle_note_pin(note_uuid, user_id, true).await?;
+        let pin = true;
+        let note = db.toggle_note_pin(note_uuid, user_id, pin).await?;
+        if note.is_some() {
+            sync_search_index(ctx, &note_id, false).await?;
+            audit::record(
+                ctx,
+                AuditRecord::new(AuditAction::NoteUpdated, user_id)
+                    .target(AuditTargetType::Note, &note_id)
+                    .changes(audit::diff(
+                        &serde_json::json!({}),
+                        &serde_json::json!({ "isPinned": pin }),
+                    )),
+            )
+            .await;
+        }
 
         // Handle the Option<Note> return type
         note.ok_or_else(|| AppError::UserNotFound.into())
@@ -303,6 +1043,7 @@ impl MutationRoot {
         input: MoveToFolderInput,
     ) -> Result<Note> {
         let (user_id, _user) = require_auth(ctx)?;
+        input.validate().map_err(AppError::from)?;
         let db = ctx.data::<Database>()?;
 
         // Parse note ID to UUID (prefix with underscore to indicate intentionally unused)
@@ -324,7 +1065,7 @@ impl MutationRoot {
         // Use create_note_with_folder method to move the note
         // First get the existing note
         let existing_note = db
-            .get_note_by_id(&note_id)
+            .get_user_note(&note_id, user_id)
             .await?
             .ok_or(AppError::UserNotFound)?;
 
@@ -341,12 +1082,215 @@ impl MutationRoot {
             .await?;
 
         // Delete the old note
-        db.delete_note(&note_id).await?;
+        db.delete_note(&note_id, user_id).await?;
+        sync_search_index(ctx, &note.id, false).await?;
+        sync_search_index(ctx, &note_id, true).await?;
+        // Moving re-creates the note under a new ID
+        audit::record(
+            ctx,
+            AuditRecord::new(AuditAction::NoteUpdated, user_id)
+                .target(AuditTargetType::Note, &note.id)
+                .changes(audit::diff(
+                    &serde_json::json!({
+                        "id": note_id,
+                        "folderId": existing_note.folder.as_ref().map(|folder| &folder.id),
+                    }),
+                    &serde_json::json!({ "id": note.id, "folderId": input.target_folder_id }),
+                )),
+        )
+        .await;
 
         Ok(note)
     }
 }
 
+/// Create a note for `user_id`, with the given title or one generated from the content
+async fn create_user_note(
+    ctx: &Context<'_>,
+    user_id: uuid::Uuid,
+    input: NoteInput,
+) -> Result<Note> {
+    // Validate input
+    input.validate().map_err(AppError::from)?;
+
+    // Smart auto-title generation
+    let title = match input.title {
+        Some(title) if !title.trim().is_empty() => title,
+        _ => generate_smart_title(&input.content),
+    };
+
+    let db = ctx.data::<Database>()?;
+    let language = input.language.as_deref();
+    check_search_language(db, "language", language).await?;
+    let folder_id = parse_optional_uuid(input.folder_id.as_deref())?;
+    check_folder_owner(db, user_id, folder_id).await?;
+
+    // Create note for authenticated user
+    let note = db
+        .create_note_for_user(user_id, &title, &input.content, language, folder_id)
+        .await?;
+    sync_search_index(ctx, &note.id, false).await?;
+    audit::record(
+        ctx,
+        AuditRecord::new(AuditAction::NoteCreated, user_id)
+            .target(AuditTargetType::Note, &note.id)
+            .changes(audit::diff(&serde_json::json!({}), &note_snapshot(&note))),
+    )
+    .await;
+    Ok(note)
+}
+
+/// Report a created, changed or deleted note to the search backend
+async fn sync_search_index(ctx: &Context<'_>, note_id: &str, removed: bool) -> Result<()> {
+    let backend = ctx.data::<SharedSearchBackend>()?;
+    if let Ok(note_id) = uuid::Uuid::parse_str(note_id) {
+        sync_note(backend.as_ref(), note_id, removed).await;
+    }
+    Ok(())
+}
+
+/// Search results returned when `limit` is not given
+const DEFAULT_SEARCH_LIMIT: i32 = 20;
+/// Recently viewed notes returned when no limit is given
+const DEFAULT_RECENTLY_VIEWED_LIMIT: i32 = 10;
+/// Activity events returned when no limit is given
+const DEFAULT_ACTIVITY_LIMIT: i32 = 20;
+
+/// Parse an optional UUID argument
+pub(crate) fn parse_optional_uuid(value: Option<&str>) -> Result<Option<uuid::Uuid>, AppError> {
+    value
+        .map(|id| {
+            uuid::Uuid::parse_str(id).map_err(|_| AppError::InvalidUuid {
+                uuid: id.to_string(),
+            })
+        })
+        .transpose()
+}
+
+/// Parse an optional RFC3339 timestamp argument, reporting failures against `field`
+pub(crate) fn parse_optional_timestamp(
+    field: &str,
+    value: Option<&str>,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, AppError> {
+    value
+        .map(|timestamp| {
+            chrono::DateTime::parse_from_rfc3339(timestamp)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .map_err(|_| AppError::InvalidInput {
+                    errors: vec![FieldError::new(
+                        field,
+                        "INVALID_TIMESTAMP",
+                        "Must be an RFC3339 timestamp",
+                    )],
+                })
+        })
+        .transpose()
+}
+
+#[ComplexObject]
+impl SmartFolder {
+    /// 📋 Notes currently matching the saved search, best match first
+    async fn notes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Maximum number of notes (1-100, defaults to 20)")] limit: Option<i32>,
+        #[graphql(desc = "Number of notes to skip (defaults to 0)")] offset: Option<i32>,
+    ) -> Result<Vec<Note>> {
+        let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        let offset = offset.unwrap_or(0);
+        if !(1..=100).contains(&limit) || offset < 0 {
+            return Err(AppError::ValidationError {
+                message: "Limit must be between 1 and 100 and offset cannot be negative"
+                    .to_string(),
+            }
+            .into());
+        }
+
+        let ast = parse_search_query("query", &self.query)?;
+        let text = ast.text();
+        let mut filters = SearchFilters {
+            folder_id: parse_optional_uuid(self.folder_id.as_deref())?,
+            include_subfolders: self.include_subfolders,
+            ..SearchFilters::default()
+        };
+        ast.apply_filters(&mut filters);
+
+        let backend = ctx.data::<SharedSearchBackend>()?;
+        let results = backend
+            .search(
+                self.user_id,
+                &text,
+                &SearchQuery::parse(&text),
+                &filters,
+                self.fuzzy.then_some(DEFAULT_FUZZY_THRESHOLD),
+                limit.into(),
+                offset.into(),
+            )
+            .await?;
+        Ok(results.into_iter().map(|result| result.note).collect())
+    }
+}
+
+/// Reject a folder ID that is not one of the user's folders
+async fn check_folder_owner(
+    db: &Database,
+    user_id: uuid::Uuid,
+    folder_id: Option<uuid::Uuid>,
+) -> Result<(), AppError> {
+    let Some(folder_id) = folder_id else {
+        return Ok(());
+    };
+    if db.get_folder_by_id(folder_id, user_id).await?.is_some() {
+        return Ok(());
+    }
+    Err(AppError::InvalidInput {
+        errors: vec![FieldError::new("folderId", "NOT_FOUND", "Folder not found")],
+    })
+}
+
+/// Parse a query-language search, reporting syntax errors against `field`
+fn parse_search_query(field: &str, q: &str) -> Result<QueryAst, AppError> {
+    let invalid = |code: &str, message: String| AppError::InvalidInput {
+        errors: vec![FieldError::new(field, code, message)],
+    };
+    if q.chars().count() as u64 > SEARCH_QUERY_MAX_LENGTH {
+        return Err(invalid(
+            "LENGTH",
+            format!(
+                "Search query must be at most {} characters",
+                SEARCH_QUERY_MAX_LENGTH
+            ),
+        ));
+    }
+    QueryAst::parse(q).map_err(|e| invalid("INVALID_QUERY", e.to_string()))
+}
+
+/// Reject a search language that is not a text search configuration
+async fn check_search_language(
+    db: &Database,
+    field: &str,
+    language: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(language) = language else {
+        return Ok(());
+    };
+    if db
+        .search_languages()
+        .await?
+        .iter()
+        .any(|name| name == language)
+    {
+        return Ok(());
+    }
+    Err(AppError::InvalidInput {
+        errors: vec![FieldError::new(
+            field,
+            "UNKNOWN_LANGUAGE",
+            format!("Unknown search language '{}'", language),
+        )],
+    })
+}
+
 /// 🎯 Enhanced Smart Auto-Title Generation
 fn generate_smart_title(content: &str) -> String {
     const MAX_LENGTH: usize = 50;
@@ -357,9 +1301,7 @@ fn generate_smart_title(content: &str) -> String {
         .next()
         .unwrap_or(content)
         .trim()
-        .replace('\n', " ")
-        .replace('\r', " ")
-        .replace('\t', " ")
+        .replace(['\n', '\r', '\t'], " ")
         .split_whitespace()
         .collect::<Vec<_>>()
         .join(" ");
@@ -437,21 +1379,397 @@ fn find_word_boundary(text: &str, max_len: usize) -> Option<usize> {
         .filter(|&pos| pos >= min_length)
 }
 
-// Helper validation functions
-fn validate_note_input(input: &NoteInput) -> AppResult<()> {
-    if input.content.is_empty() {
-        return Err(AppError::InvalidContent {
-            message: "Content cannot be empty".to_string(),
-        });
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use async_graphql::{Request, Schema, Variables};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::config::{AuthConfig, MailConfig, SearchConfig};
+    use crate::database::test_database;
+    use crate::mailer::{outbox_messages, MailTransportKind};
+    use crate::views::ViewTracker;
+    use crate::web::AppSchema;
+
+    const PASSWORD: &str = "Passw0rd!long";
+
+    /// Schema wired like `main`, with mail written to a temporary outbox
+    struct TestApp {
+        schema: AppSchema,
+        db: Database,
+        auth: AuthService,
+        outbox: PathBuf,
+        users: std::sync::Mutex<Vec<uuid::Uuid>>,
     }
 
-    if let Some(title) = &input.title {
-        if title.len() > 200 {
-            return Err(AppError::InvalidTitle {
-                message: "Title too long (max 200 characters)".to_string(),
+    impl TestApp {
+        async fn new() -> Self {
+            let db = test_database().await;
+            let auth = AuthService::new(&AuthConfig {
+                bcrypt_cost: 4,
+                ..AuthConfig::default()
             });
+            let outbox = std::env::temp_dir().join(format!("outbox-{}", uuid::Uuid::new_v4()));
+            let mailer = Mailer::from_config(&MailConfig {
+                transport: MailTransportKind::File,
+                outbox_dir: outbox.clone(),
+                ..MailConfig::default()
+            })
+            .unwrap();
+            let search_backend =
+                crate::search_backend::open_backend(&SearchConfig::default(), db.clone())
+                    .await
+                    .unwrap();
+            let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+                .data(db.clone())
+                .data(auth.clone())
+                .data(mailer)
+                .data(AccountsConfig::default())
+                .data(search_backend)
+                .data(ViewTracker::new())
+                .finish();
+
+            Self {
+                schema,
+                db,
+                auth,
+                outbox,
+                users: Default::default(),
+            }
+        }
+
+        /// Register a fresh account, returning its token
+        async fn sign_up(&self) -> String {
+            let email = format!("{}@resolver-test.invalid", uuid::Uuid::new_v4());
+            let data = self
+                .execute(
+                    None,
+                    "mutation($input: RegisterInput!) { register(input: $input) { token user { id } } }",
+                    json!({ "input": { "email": email, "password": PASSWORD } }),
+                )
+                .await
+                .expect("register");
+            let id = data["register"]["user"]["id"].as_str().unwrap();
+            self.users.lock().unwrap().push(id.parse().unwrap());
+            data["register"]["token"].as_str().unwrap().to_string()
+        }
+
+        /// Run an operation as the holder of `token`, returning `data` or the error messages
+        async fn execute(
+            &self,
+            token: Option<&str>,
+            query: &str,
+            variables: Value,
+        ) -> Result<Value, Vec<String>> {
+            let header = token.map(|token| format!("Bearer {}", token));
+            let auth_context = self
+                .auth
+                .create_auth_context(header.as_deref(), &self.db)
+                .await;
+            let request = Request::new(query)
+                .variables(Variables::from_json(variables))
+                .data(auth_context);
+            let response = self.schema.execute(request).await;
+            if response.errors.is_empty() {
+                Ok(response.data.into_json().unwrap())
+            } else {
+                Err(response.errors.into_iter().map(|e| e.message).collect())
+            }
+        }
+
+        /// Delete every account created by [`TestApp::sign_up`] and the outbox
+        async fn cleanup(&self) {
+            let users = std::mem::take(&mut *self.users.lock().unwrap());
+            sqlx::query("DELETE FROM users WHERE id = ANY($1)")
+                .bind(users)
+                .execute(self.db.pool())
+                .await
+                .unwrap();
+            let _ = std::fs::remove_dir_all(&self.outbox);
+        }
+
+        /// Create a note, returning its ID
+        async fn create_note(&self, token: &str, input: Value) -> String {
+            let data = self
+                .execute(
+                    Some(token),
+                    "mutation($input: NoteInput!) { createNote(input: $input) { id } }",
+                    json!({ "input": input }),
+                )
+                .await
+                .expect("createNote");
+            data["createNote"]["id"].as_str().unwrap().to_string()
+        }
+
+        /// Create a folder, returning its ID
+        async fn create_folder(&self, token: &str, name: &str) -> String {
+            let data = self
+                .execute(
+                    Some(token),
+                    "mutation($input: CreateFolderInput!) { createFolder(input: $input) { id } }",
+                    json!({ "input": { "name": name } }),
+                )
+                .await
+                .expect("createFolder");
+            data["createFolder"]["id"].as_str().unwrap().to_string()
+        }
+
+        /// Pin or unpin a note
+        async fn toggle_pin(&self, token: &str, note_id: &str) {
+            self.execute(
+                Some(token),
+                "mutation($id: String!) { toggleNotePin(noteId: $id) { id } }",
+                json!({ "id": note_id }),
+            )
+            .await
+            .expect("toggleNotePin");
+        }
+
+        /// Save a search, returning its ID
+        async fn create_saved_search(&self, token: &str, input: Value) -> String {
+            let data = self
+                .execute(
+                    Some(token),
+                    "mutation($input: CreateSavedSearchInput!) { createSavedSearch(input: $input) { id } }",
+                    json!({ "input": input }),
+                )
+                .await
+                .expect("createSavedSearch");
+            data["createSavedSearch"]["id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        }
+
+        /// Titles of the notes a smart folder currently matches, as seen by `token`
+        async fn smart_folder_titles(&self, token: &str, id: &str) -> Option<Vec<String>> {
+            let data = self
+                .execute(
+                    Some(token),
+                    "query($id: String!) { smartFolder(id: $id) { notes { title } } }",
+                    json!({ "id": id }),
+                )
+                .await
+                .expect("smartFolder");
+            let notes = data["smartFolder"]["notes"].as_array()?;
+            let mut titles: Vec<String> = notes
+                .iter()
+                .map(|note| note["title"].as_str().unwrap().to_string())
+                .collect();
+            titles.sort();
+            Some(titles)
         }
     }
 
-    Ok(())
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL at DATABASE_URL"]
+    async fn test_email_change_is_confirmed_with_the_delivered_token() {
+        let app = TestApp::new().await;
+        let token = app.sign_up().await;
+        let new_email = format!("{}@resolver-test.invalid", uuid::Uuid::new_v4());
+
+        let requested = app
+            .execute(
+                Some(&token),
+                "mutation($input: ChangeEmailInput!) { changeEmail(input: $input) }",
+                json!({ "input": { "newEmail": new_email, "password": PASSWORD } }),
+            )
+            .await;
+        let messages = outbox_messages(&app.outbox);
+        let delivered = messages
+            .last()
+            .and_then(|message| message.lines().rev().find(|line| !line.trim().is_empty()))
+            .map(|line| line.trim().to_string())
+            .unwrap_or_default();
+        let confirmed = app
+            .execute(
+                None,
+                "mutation($token: String!) { confirmEmailChange(token: $token) { user { email } } }",
+                json!({ "token": delivered }),
+            )
+            .await;
+        app.cleanup().await;
+
+        assert_eq!(requested.unwrap()["changeEmail"], json!(true));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains(&format!("To: {}", new_email)));
+        assert_eq!(
+            confirmed.unwrap()["confirmEmailChange"]["user"]["email"],
+            json!(new_email)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL at DATABASE_URL"]
+    async fn test_saved_search_reruns_its_query_and_filters() {
+        let app = TestApp::new().await;
+        let token = app.sign_up().await;
+        let folder = app.create_folder(&token, "Orchard").await;
+        for (title, content, folder_id, pinned) in [
+            ("Pinned in folder", "kiwi harvest", Some(&folder), true),
+            ("Unpinned in folder", "kiwi harvest", Some(&folder), false),
+            ("Pinned elsewhere", "kiwi harvest", None, true),
+            ("Other fruit", "banana harvest", Some(&folder), true),
+        ] {
+            let input = json!({ "title": title, "content": content, "folderId": folder_id });
+            let note = app.create_note(&token, input).await;
+            if pinned {
+                app.toggle_pin(&token, &note).await;
+            }
+        }
+
+        let saved = app
+            .create_saved_search(
+                &token,
+                json!({ "name": "Kiwis", "query": "kiwi pinned:true", "folderId": folder }),
+            )
+            .await;
+        let before = app.smart_folder_titles(&token, &saved).await;
+        // Results are computed on read, so a new matching note shows up
+        let input = json!({ "title": "Added later", "content": "kiwi", "folderId": folder });
+        let note = app.create_note(&token, input).await;
+        app.toggle_pin(&token, &note).await;
+        let after = app.smart_folder_titles(&token, &saved).await;
+        app.cleanup().await;
+
+        assert_eq!(before, Some(vec!["Pinned in folder".to_string()]));
+        assert_eq!(
+            after,
+            Some(vec![
+                "Added later".to_string(),
+                "Pinned in folder".to_string()
+            ])
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL at DATABASE_URL"]
+    async fn test_saved_searches_are_scoped_to_their_owner() {
+        let app = TestApp::new().await;
+        let owner = app.sign_up().await;
+        let other = app.sign_up().await;
+        app.create_note(&owner, json!({ "title": "Mine", "content": "kiwi" }))
+            .await;
+        app.create_note(&other, json!({ "title": "Theirs", "content": "kiwi" }))
+            .await;
+        let other_folder = app.create_folder(&other, "Private").await;
+
+        let saved = app
+            .create_saved_search(&owner, json!({ "name": "Kiwis", "query": "kiwi" }))
+            .await;
+        let owner_titles = app.smart_folder_titles(&owner, &saved).await;
+        let other_titles = app.smart_folder_titles(&other, &saved).await;
+        let other_list = app
+            .execute(Some(&other), "{ smartFolders { id } }", json!({}))
+            .await;
+        let updated = app
+            .execute(
+                Some(&other),
+                "mutation($id: String!) { updateSavedSearch(id: $id, input: { query: \"theirs\" }) { id } }",
+                json!({ "id": saved }),
+            )
+            .await;
+        let deleted = app
+            .execute(
+                Some(&other),
+                "mutation($id: String!) { deleteSavedSearch(id: $id) }",
+                json!({ "id": saved }),
+            )
+            .await;
+        let into_other_folder = app
+            .execute(
+                Some(&owner),
+                "mutation($input: CreateSavedSearchInput!) { createSavedSearch(input: $input) { id } }",
+                json!({ "input": { "name": "Theirs", "query": "kiwi", "folderId": other_folder } }),
+            )
+            .await;
+        let owner_titles_after = app.smart_folder_titles(&owner, &saved).await;
+        app.cleanup().await;
+
+        assert_eq!(owner_titles, Some(vec!["Mine".to_string()]));
+        assert_eq!(other_titles, None);
+        assert_eq!(other_list.unwrap()["smartFolders"], json!([]));
+        assert_eq!(updated.unwrap()["updateSavedSearch"], Value::Null);
+        assert_eq!(deleted.unwrap()["deleteSavedSearch"], json!(false));
+        assert!(into_other_folder.is_err());
+        assert_eq!(owner_titles_after, owner_titles);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL at DATABASE_URL"]
+    async fn test_unknown_search_languages_are_rejected() {
+        let app = TestApp::new().await;
+        let token = app.sign_up().await;
+
+        let note = app
+            .execute(
+                Some(&token),
+                "mutation($input: NoteInput!) { createNote(input: $input) { id } }",
+                json!({ "input": { "content": "Hello", "language": "klingon" } }),
+            )
+            .await;
+        let profile = app
+            .execute(
+                Some(&token),
+                "mutation($input: UpdateProfileInput!) { updateProfile(input: $input) { id } }",
+                json!({ "input": { "language": "klingon" } }),
+            )
+            .await;
+        let notes = app
+            .execute(Some(&token), "{ notes { id } }", json!({}))
+            .await;
+        app.cleanup().await;
+
+        let expected =
+            vec!["Invalid input: language: Unknown search language 'klingon'".to_string()];
+        assert_eq!(note.unwrap_err(), expected);
+        assert_eq!(profile.unwrap_err(), expected);
+        assert_eq!(notes.unwrap()["notes"], json!([]));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL at DATABASE_URL"]
+    async fn test_note_language_overrides_the_users() {
+        let app = TestApp::new().await;
+        let token = app.sign_up().await;
+        app.execute(
+            Some(&token),
+            "mutation { updateProfile(input: { language: \"simple\" }) { id } }",
+            json!({}),
+        )
+        .await
+        .expect("updateProfile");
+        app.create_note(
+            &token,
+            json!({ "title": "Inherited", "content": "running shoes" }),
+        )
+        .await;
+        let input =
+            json!({ "title": "Overridden", "content": "running shoes", "language": "english" });
+        let overridden = app.create_note(&token, input).await;
+
+        // `simple` keeps "running" as is, `english` stems it to "run"
+        let found = app
+            .execute(
+                Some(&token),
+                "{ searchNotes(q: \"run\") { results { note { title } } } }",
+                json!({}),
+            )
+            .await;
+        let stored: String =
+            sqlx::query_scalar("SELECT search_language::TEXT FROM notes WHERE id = $1::uuid")
+                .bind(&overridden)
+                .fetch_one(app.db.pool())
+                .await
+                .unwrap();
+        app.cleanup().await;
+
+        assert_eq!(stored, "english");
+        assert_eq!(
+            found.unwrap()["searchNotes"]["results"],
+            json!([{ "note": { "title": "Overridden" } }])
+        );
+    }
 }
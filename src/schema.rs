@@ -0,0 +1,508 @@
+//! # Schema Export and Compatibility Checks
+//!
+//! The GraphQL schema as SDL for client code generation, without going through
+//! introspection:
+//!
+//! - `GET /schema.graphql` serves the SDL of the running server
+//! - `backend --print-schema > schema.graphql` writes it without a database or
+//!   configuration
+//! - `backend --diff-schema old.graphql new.graphql` lists the changes between
+//!   two SDL files and exits with status 1 if any of them breaks existing clients
+//!
+//! ## Breaking Changes
+//!
+//! A change is breaking when a query that was valid against the old schema can
+//! fail against the new one: removed types, fields, arguments, enum values or
+//! union members, a field type that is no longer compatible, or a new required
+//! argument or input field. Additions clients may not handle (new enum values and
+//! union members, changed argument defaults) are reported as dangerous.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use async_graphql::parser::{
+    parse_schema,
+    types::{
+        BaseType, FieldDefinition, InputValueDefinition, Type, TypeDefinition, TypeKind,
+        TypeSystemDefinition,
+    },
+    Positioned,
+};
+use async_graphql::{EmptySubscription, Schema};
+
+use crate::resolvers::{MutationRoot, QueryRoot};
+
+/// 📜 SDL of the API schema (independent of runtime data and limits)
+pub fn sdl() -> String {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .finish()
+        .sdl()
+}
+
+/// How a schema change affects existing clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Existing operations can fail
+    Breaking,
+    /// Existing operations stay valid but may see values they do not expect
+    Dangerous,
+    /// Backwards compatible
+    Safe,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Breaking => write!(f, "❌ BREAKING "),
+            Severity::Dangerous => write!(f, "⚠️ DANGEROUS"),
+            Severity::Safe => write!(f, "✅ SAFE     "),
+        }
+    }
+}
+
+/// A single difference between two schemas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl SchemaChange {
+    fn new(severity: Severity, message: String) -> Self {
+        Self { severity, message }
+    }
+}
+
+/// 🔍 Compare two SDL documents, returning changes ordered by severity
+pub fn diff(old: &str, new: &str) -> Result<Vec<SchemaChange>, String> {
+    let old_types = type_definitions(old).map_err(|e| format!("old schema: {}", e))?;
+    let new_types = type_definitions(new).map_err(|e| format!("new schema: {}", e))?;
+
+    let mut changes = Vec::new();
+    for (name, old_type) in &old_types {
+        match new_types.get(name) {
+            Some(new_type) => diff_type(name, old_type, new_type, &mut changes),
+            None => changes.push(SchemaChange::new(
+                Severity::Breaking,
+                format!("Type {} was removed", name),
+            )),
+        }
+    }
+    for name in new_types
+        .keys()
+        .filter(|name| !old_types.contains_key(*name))
+    {
+        changes.push(SchemaChange::new(
+            Severity::Safe,
+            format!("Type {} was added", name),
+        ));
+    }
+
+    changes.sort_by_key(|change| change.severity);
+    Ok(changes)
+}
+
+/// 🛠️ Handle `--print-schema` and `--diff-schema`, returning the exit code
+///
+/// Returns `None` when the arguments do not ask for a schema command, in which
+/// case the server starts as usual.
+pub fn run_command(args: &[String]) -> Option<i32> {
+    match args {
+        [flag] if flag == "--print-schema" => {
+            print!("{}", sdl());
+            Some(0)
+        }
+        [flag, old_path, new_path] if flag == "--diff-schema" => Some(run_diff(old_path, new_path)),
+        [flag, ..] if flag == "--diff-schema" => {
+            eprintln!("❌ Usage: --diff-schema <old.graphql> <new.graphql>");
+            Some(2)
+        }
+        _ => None,
+    }
+}
+
+fn run_diff(old_path: &str, new_path: &str) -> i32 {
+    let read = |path: &str| {
+        std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))
+    };
+    let changes = match read(old_path).and_then(|old| diff(&old, &read(new_path)?)) {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+
+    if changes.is_empty() {
+        println!("No schema changes");
+        return 0;
+    }
+    for change in &changes {
+        println!("{}  {}", change.severity, change.message);
+    }
+
+    let breaking = changes
+        .iter()
+        .filter(|change| change.severity == Severity::Breaking)
+        .count();
+    if breaking > 0 {
+        eprintln!("\n{} breaking change(s)", breaking);
+        1
+    } else {
+        0
+    }
+}
+
+/// Parse SDL into its type definitions, keyed by name
+fn type_definitions(sdl: &str) -> Result<BTreeMap<String, TypeDefinition>, String> {
+    let document = parse_schema(sdl).map_err(|e| e.to_string())?;
+    Ok(document
+        .definitions
+        .into_iter()
+        .filter_map(|definition| match definition {
+            TypeSystemDefinition::Type(definition) => Some(definition.node),
+            _ => None,
+        })
+        .map(|definition| (definition.name.node.to_string(), definition))
+        .collect())
+}
+
+fn diff_type(name: &str, old: &TypeDefinition, new: &TypeDefinition, out: &mut Vec<SchemaChange>) {
+    match (&old.kind, &new.kind) {
+        (TypeKind::Scalar, TypeKind::Scalar) => {}
+        (TypeKind::Object(old), TypeKind::Object(new)) => {
+            diff_names(
+                name,
+                "interface",
+                &old.implements,
+                &new.implements,
+                Severity::Safe,
+                out,
+            );
+            diff_fields(name, &old.fields, &new.fields, out);
+        }
+        (TypeKind::Interface(old), TypeKind::Interface(new)) => {
+            diff_fields(name, &old.fields, &new.fields, out);
+        }
+        (TypeKind::Union(old), TypeKind::Union(new)) => {
+            diff_names(
+                name,
+                "member",
+                &old.members,
+                &new.members,
+                Severity::Dangerous,
+                out,
+            );
+        }
+        (TypeKind::Enum(old), TypeKind::Enum(new)) => {
+            let old_values = old.values.iter().map(|value| &value.node.value);
+            let new_values = new.values.iter().map(|value| &value.node.value);
+            diff_names(
+                name,
+                "value",
+                &old_values.cloned().collect::<Vec<_>>(),
+                &new_values.cloned().collect::<Vec<_>>(),
+                Severity::Dangerous,
+                out,
+            );
+        }
+        (TypeKind::InputObject(old), TypeKind::InputObject(new)) => {
+            diff_input_values(name, "input field", &old.fields, &new.fields, out);
+        }
+        (old_kind, new_kind) => out.push(SchemaChange::new(
+            Severity::Breaking,
+            format!(
+                "Type {} changed from {} to {}",
+                name,
+                kind_name(old_kind),
+                kind_name(new_kind)
+            ),
+        )),
+    }
+}
+
+/// Removed names are breaking, added ones get `added_severity`
+fn diff_names<T: fmt::Display + Ord>(
+    type_name: &str,
+    what: &str,
+    old: &[Positioned<T>],
+    new: &[Positioned<T>],
+    added_severity: Severity,
+    out: &mut Vec<SchemaChange>,
+) {
+    let old = old.iter().map(|name| &name.node).collect::<BTreeSet<_>>();
+    let new = new.iter().map(|name| &name.node).collect::<BTreeSet<_>>();
+
+    for removed in old.difference(&new) {
+        out.push(SchemaChange::new(
+            Severity::Breaking,
+            format!(
+                "{} {} was removed from {}",
+                capitalized(what),
+                removed,
+                type_name
+            ),
+        ));
+    }
+    for added in new.difference(&old) {
+        out.push(SchemaChange::new(
+            added_severity,
+            format!("{} {} was added to {}", capitalized(what), added, type_name),
+        ));
+    }
+}
+
+fn diff_fields(
+    type_name: &str,
+    old: &[Positioned<FieldDefinition>],
+    new: &[Positioned<FieldDefinition>],
+    out: &mut Vec<SchemaChange>,
+) {
+    for old_field in old.iter().map(|field| &field.node) {
+        let path = format!("{}.{}", type_name, old_field.name.node);
+        let Some(new_field) = new
+            .iter()
+            .map(|field| &field.node)
+            .find(|field| field.name.node == old_field.name.node)
+        else {
+            out.push(SchemaChange::new(
+                Severity::Breaking,
+                format!("Field {} was removed", path),
+            ));
+            continue;
+        };
+
+        let (old_ty, new_ty) = (&old_field.ty.node, &new_field.ty.node);
+        if old_ty != new_ty {
+            let severity = if is_safe_output_change(old_ty, new_ty) {
+                Severity::Safe
+            } else {
+                Severity::Breaking
+            };
+            out.push(SchemaChange::new(
+                severity,
+                format!("Field {} changed type from {} to {}", path, old_ty, new_ty),
+            ));
+        }
+        diff_input_values(
+            &path,
+            "argument",
+            &old_field.arguments,
+            &new_field.arguments,
+            out,
+        );
+    }
+
+    for new_field in new.iter().map(|field| &field.node) {
+        if !old
+            .iter()
+            .any(|field| field.node.name.node == new_field.name.node)
+        {
+            out.push(SchemaChange::new(
+                Severity::Safe,
+                format!("Field {}.{} was added", type_name, new_field.name.node),
+            ));
+        }
+    }
+}
+
+/// Arguments and input object fields share the same compatibility rules
+fn diff_input_values(
+    owner: &str,
+    what: &str,
+    old: &[Positioned<InputValueDefinition>],
+    new: &[Positioned<InputValueDefinition>],
+    out: &mut Vec<SchemaChange>,
+) {
+    for old_value in old.iter().map(|value| &value.node) {
+        let Some(new_value) = new
+            .iter()
+            .map(|value| &value.node)
+            .find(|value| value.name.node == old_value.name.node)
+        else {
+            out.push(SchemaChange::new(
+                Severity::Breaking,
+                format!(
+                    "{} {} was removed from {}",
+                    capitalized(what),
+                    old_value.name.node,
+                    owner
+                ),
+            ));
+            continue;
+        };
+
+        let (old_ty, new_ty) = (&old_value.ty.node, &new_value.ty.node);
+        if old_ty != new_ty {
+            let severity = if is_safe_input_change(old_ty, new_ty) {
+                Severity::Safe
+            } else {
+                Severity::Breaking
+            };
+            out.push(SchemaChange::new(
+                severity,
+                format!(
+                    "{} {} on {} changed type from {} to {}",
+                    capitalized(what),
+                    old_value.name.node,
+                    owner,
+                    old_ty,
+                    new_ty
+                ),
+            ));
+        }
+
+        let old_default = old_value.default_value.as_ref().map(|value| &value.node);
+        let new_default = new_value.default_value.as_ref().map(|value| &value.node);
+        if old_default != new_default {
+            out.push(SchemaChange::new(
+                Severity::Dangerous,
+                format!(
+                    "{} {} on {} changed its default value",
+                    capitalized(what),
+                    old_value.name.node,
+                    owner
+                ),
+            ));
+        }
+    }
+
+    for new_value in new.iter().map(|value| &value.node) {
+        if old
+            .iter()
+            .any(|value| value.node.name.node == new_value.name.node)
+        {
+            continue;
+        }
+        let required = !new_value.ty.node.nullable && new_value.default_value.is_none();
+        out.push(SchemaChange::new(
+            if required {
+                Severity::Breaking
+            } else {
+                Severity::Safe
+            },
+            format!(
+                "{} {} {} was added to {}",
+                if required { "Required" } else { "Optional" },
+                what,
+                new_value.name.node,
+                owner
+            ),
+        ));
+    }
+}
+
+/// Clients reading a field still cope when it becomes non-null, not the reverse
+fn is_safe_output_change(old: &Type, new: &Type) -> bool {
+    (old.nullable || !new.nullable) && same_base(&old.base, &new.base, is_safe_output_change)
+}
+
+/// Clients sending a value still cope when it becomes nullable, not the reverse
+fn is_safe_input_change(old: &Type, new: &Type) -> bool {
+    (new.nullable || !old.nullable) && same_base(&old.base, &new.base, is_safe_input_change)
+}
+
+fn same_base(old: &BaseType, new: &BaseType, is_safe: fn(&Type, &Type) -> bool) -> bool {
+    match (old, new) {
+        (BaseType::Named(old), BaseType::Named(new)) => old == new,
+        (BaseType::List(old), BaseType::List(new)) => is_safe(old, new),
+        _ => false,
+    }
+}
+
+/// `input field` -> `Input field`, for the start of a message
+fn capitalized(what: &str) -> String {
+    let mut chars = what.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn kind_name(kind: &TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Scalar => "scalar",
+        TypeKind::Object(_) => "object",
+        TypeKind::Interface(_) => "interface",
+        TypeKind::Union(_) => "union",
+        TypeKind::Enum(_) => "enum",
+        TypeKind::InputObject(_) => "input object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"
+        type Note { id: ID! title: String! tags: [String!] }
+        enum Role { USER ADMIN }
+        input NoteInput { content: String! }
+        type Query { notes(limit: Int): [Note!]! }
+    "#;
+
+    fn changes(new: &str) -> Vec<(Severity, String)> {
+        diff(OLD, new)
+            .unwrap()
+            .into_iter()
+            .map(|change| (change.severity, change.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_schemas_have_no_changes() {
+        assert!(changes(OLD).is_empty());
+    }
+
+    #[test]
+    fn test_breaking_changes() {
+        let new = r#"
+            type Note { id: ID! title: String tags: [Int!] }
+            enum Role { USER }
+            input NoteInput { content: String! folderId: ID! }
+            type Query { notes(limit: Int!): [Note!]! }
+        "#;
+        assert_eq!(
+            changes(new),
+            vec![
+                (
+                    Severity::Breaking,
+                    "Field Note.title changed type from String! to String".to_string()
+                ),
+                (
+                    Severity::Breaking,
+                    "Field Note.tags changed type from [String!] to [Int!]".to_string()
+                ),
+                (
+                    Severity::Breaking,
+                    "Required input field folderId was added to NoteInput".to_string()
+                ),
+                (
+                    Severity::Breaking,
+                    "Argument limit on Query.notes changed type from Int to Int!".to_string()
+                ),
+                (
+                    Severity::Breaking,
+                    "Value ADMIN was removed from Role".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compatible_changes() {
+        let new = r#"
+            type Note { id: ID! title: String! tags: [String!]! pinned: Boolean }
+            enum Role { USER ADMIN GUEST }
+            input NoteInput { content: String folderId: ID }
+            type Query { notes(limit: Int, offset: Int = 0): [Note!]! }
+        "#;
+        assert!(changes(new)
+            .iter()
+            .all(|(severity, _)| *severity != Severity::Breaking));
+        assert!(changes(new).contains(&(
+            Severity::Dangerous,
+            "Value GUEST was added to Role".to_string()
+        )));
+    }
+}
@@ -0,0 +1,311 @@
+//! # Full-Text Search
+//!
+//! Query preparation and result rendering for `searchNotes`.
+//!
+//! ## Query Syntax
+//!
+//! Queries use PostgreSQL's `websearch_to_tsquery` syntax, extended with
+//! prefix terms:
+//!
+//! - `rust async`: both words (stemmed, so `programs` matches `programming`)
+//! - `"borrow checker"`: the exact phrase
+//! - `rust or go`, `rust -java`: alternatives and exclusions
+//! - `own*`: words starting with `own` (`-own*` excludes them)
+//!
+//! `searchNotes(q:)` adds field operators on top (see
+//! [`crate::query_language`]); they become [`SearchFilters`] and the remaining
+//! text is searched as above.
+//!
+//! Words are stemmed with each note's search language (its own `language`, else
+//! its owner's), so the query is parsed once per language among the user's notes.
+//! Prefix terms always use the `simple` configuration.
+//!
+//! ## Fuzzy Matching
+//!
+//! With `fuzzy` enabled, notes whose title or content contains a run of words
+//! similar to the query (`pg_trgm` word similarity above a threshold) match
+//! too, so `postgress` finds `PostgreSQL`. The score is then the full-text rank
+//! plus the best trigram similarity. When nothing matches the full-text query,
+//! a "did you mean" suggestion replaces each word with the most similar word
+//! from the user's own notes.
+//!
+//! ## Highlighting
+//!
+//! `ts_headline` marks matches with control characters, which are turned into
+//! `<mark>` tags after the note text is HTML-escaped, so snippets can be
+//! rendered as HTML without trusting note content.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Similarity threshold used for fuzzy matching when none is given
+pub const DEFAULT_FUZZY_THRESHOLD: f64 = 0.3;
+/// Minimum similarity for a word from the user's notes to be suggested
+pub const SUGGESTION_THRESHOLD: f64 = 0.3;
+
+/// Marks the start of a match in `ts_headline` output
+pub const HIGHLIGHT_START: char = '\u{2}';
+/// Marks the end of a match in `ts_headline` output
+pub const HIGHLIGHT_STOP: char = '\u{3}';
+/// Separates the fragments of a content headline
+pub const FRAGMENT_DELIMITER: char = '\u{1f}';
+
+/// 🔎 A search query split into what `websearch_to_tsquery` and `to_tsquery` handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Words, phrases and operators for `websearch_to_tsquery`
+    pub websearch: String,
+    /// `to_tsquery` expression for the prefix terms (`own:* & !rus:*`), or empty
+    ///
+    /// Parsed with the `simple` configuration: prefixes are neither stemmed nor
+    /// dropped as stop words (`own*` must still match `ownership`).
+    pub prefix: String,
+    /// Words to match by trigram similarity: everything except exclusions and operators
+    pub fuzzy_text: String,
+    /// Plain lowercase words that can be replaced by a "did you mean" suggestion
+    pub terms: Vec<String>,
+}
+
+impl SearchQuery {
+    /// Split prefix terms (`word*`) out of a user query; quoted phrases are kept whole
+    pub fn parse(query: &str) -> Self {
+        let mut websearch = Vec::new();
+        let mut prefix = Vec::new();
+        let mut fuzzy = Vec::new();
+        let mut terms = Vec::new();
+
+        for token in tokenize(query) {
+            match prefix_term(token) {
+                Some(term) => prefix.push(term),
+                None => websearch.push(token),
+            }
+
+            if token.starts_with('-') || token.eq_ignore_ascii_case("or") {
+                continue;
+            }
+            let words = token.trim_matches(|c| c == '"' || c == '*');
+            if !words.is_empty() {
+                fuzzy.push(words);
+            }
+            if let Some(term) = plain_term(token) {
+                terms.push(term);
+            }
+        }
+
+        Self {
+            websearch: websearch.join(" "),
+            prefix: prefix.join(" & "),
+            fuzzy_text: fuzzy.join(" "),
+            terms,
+        }
+    }
+
+    /// Rewrite `query` with corrected words, or `None` if nothing changed
+    ///
+    /// `corrections` maps terms (as in [`SearchQuery::terms`]) to their replacement.
+    pub fn did_you_mean(query: &str, corrections: &HashMap<String, String>) -> Option<String> {
+        let mut changed = false;
+        let tokens = tokenize(query)
+            .into_iter()
+            .map(|token| {
+                let correction = plain_term(token)
+                    .and_then(|term| corrections.get(&term))
+                    .filter(|correction| **correction != token.to_lowercase());
+                match correction {
+                    Some(correction) => {
+                        changed = true;
+                        correction.clone()
+                    }
+                    None => token.to_string(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        changed.then(|| tokens.join(" "))
+    }
+
+    /// Whether nothing searchable is left
+    pub fn is_empty(&self) -> bool {
+        self.websearch.trim().is_empty() && self.prefix.is_empty()
+    }
+}
+
+/// Split on whitespace outside of double quotes
+pub fn tokenize(query: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+
+    for (index, ch) in query.char_indices() {
+        if ch == '"' {
+            in_quotes = !in_quotes;
+        }
+        if ch.is_whitespace() && !in_quotes {
+            if let Some(begin) = start.take() {
+                tokens.push(&query[begin..index]);
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if let Some(begin) = start {
+        tokens.push(&query[begin..]);
+    }
+
+    tokens
+}
+
+/// Lowercase `#hashtags` in `text`, without the `#`
+///
+/// A tag starts after a `#` that does not follow a word character and runs over
+/// letters, digits, `_` and `-`, the same rule `tag:` filters use in SQL.
+pub fn hashtags(text: &str) -> Vec<String> {
+    let is_tag_char = |ch: char| ch.is_alphanumeric() || ch == '_' || ch == '-';
+    let mut tags = Vec::new();
+    let mut previous = None;
+
+    for (index, ch) in text.char_indices() {
+        let after_word = previous.is_some_and(|p: char| p.is_alphanumeric() || p == '_');
+        if ch == '#' && !after_word {
+            let rest = &text[index + 1..];
+            let end = rest.find(|c| !is_tag_char(c)).unwrap_or(rest.len());
+            if end > 0 {
+                tags.push(rest[..end].to_lowercase());
+            }
+        }
+        previous = Some(ch);
+    }
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// `own*` -> `own:*`, `-own*` -> `!own:*`; `None` for anything else
+fn prefix_term(token: &str) -> Option<String> {
+    let (negated, word) = match token.strip_prefix('-') {
+        Some(word) => (true, word),
+        None => (false, token),
+    };
+    let stem = word.strip_suffix('*')?;
+
+    // Only plain words, so the term cannot inject tsquery operators
+    if stem.is_empty() || !stem.chars().all(char::is_alphanumeric) {
+        return None;
+    }
+
+    Some(format!("{}{}:*", if negated { "!" } else { "" }, stem))
+}
+
+/// A bare word (no quotes, operators or prefix marker), lowercased
+fn plain_term(token: &str) -> Option<String> {
+    let is_plain = token.chars().all(char::is_alphanumeric) && !token.eq_ignore_ascii_case("or");
+    (is_plain && !token.is_empty()).then(|| token.to_lowercase())
+}
+
+/// 🎛️ Filters applied on top of the text match
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    pub folder_id: Option<Uuid>,
+    /// Also match notes in subfolders of `folder_id`, at any depth
+    pub include_subfolders: bool,
+    pub is_pinned: Option<bool>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    /// Lowercase hashtags (without `#`) the content must all contain
+    pub tags: Vec<String>,
+    /// Lowercase hashtags the content must not contain
+    pub excluded_tags: Vec<String>,
+    /// Lowercase folder names; the note must be in one of them (when not empty)
+    pub folder_names: Vec<String>,
+    /// Lowercase folder names the note must not be in
+    pub excluded_folder_names: Vec<String>,
+}
+
+/// Turn `ts_headline` output into HTML with `<mark>` around matches
+pub fn render_highlight(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+    for ch in headline.chars() {
+        match ch {
+            HIGHLIGHT_START => html.push_str("<mark>"),
+            HIGHLIGHT_STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(ch),
+        }
+    }
+    html
+}
+
+/// Split a content headline into its highlighted fragments
+pub fn render_snippets(headline: &str) -> Vec<String> {
+    headline
+        .split(FRAGMENT_DELIMITER)
+        .map(str::trim)
+        .filter(|fragment| !fragment.is_empty())
+        .map(render_highlight)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_terms_are_split_out() {
+        let query = SearchQuery::parse(r#"rust own* "async run*" -bor*"#);
+        assert_eq!(query.websearch, r#"rust "async run*""#);
+        assert_eq!(query.prefix, "own:* & !bor:*");
+    }
+
+    #[test]
+    fn test_prefix_terms_reject_operators() {
+        let query = SearchQuery::parse("a|b* *");
+        assert_eq!(query.websearch, "a|b* *");
+        assert_eq!(query.prefix, "");
+        assert!(SearchQuery::parse("   ").is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_text_and_terms() {
+        let query = SearchQuery::parse(r#"Postgress "query plan" or index* -mysql"#);
+        assert_eq!(query.fuzzy_text, "Postgress query plan index");
+        assert_eq!(query.terms, vec!["postgress".to_string()]);
+    }
+
+    #[test]
+    fn test_did_you_mean_replaces_plain_words() {
+        let corrections = HashMap::from([
+            ("postgress".to_string(), "postgresql".to_string()),
+            ("index".to_string(), "index".to_string()),
+        ]);
+        assert_eq!(
+            SearchQuery::did_you_mean(r#"Postgress index "query plan""#, &corrections),
+            Some(r#"postgresql index "query plan""#.to_string())
+        );
+        assert_eq!(SearchQuery::did_you_mean("index", &corrections), None);
+    }
+
+    #[test]
+    fn test_hashtags() {
+        assert_eq!(
+            hashtags("#Work notes, see issue#4 and (#q3-plans) ##work #"),
+            vec!["q3-plans".to_string(), "work".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_highlight_escapes_note_text() {
+        let headline = format!("<b>{}Rust{}</b> & more", HIGHLIGHT_START, HIGHLIGHT_STOP);
+        assert_eq!(
+            render_highlight(&headline),
+            "&lt;b&gt;<mark>Rust</mark>&lt;/b&gt; &amp; more"
+        );
+    }
+}
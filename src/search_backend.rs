@@ -0,0 +1,207 @@
+//! # Search Backends
+//!
+//! `searchNotes` runs against a [`SearchBackend`], chosen with `search.backend`:
+//!
+//! - `postgres` (default): PostgreSQL full-text search over the stored
+//!   `search_vector` column, always in sync with the notes table
+//! - `tantivy`: an embedded [Tantivy](https://github.com/quickwit-oss/tantivy)
+//!   index on local disk (`search.index_dir`) with BM25 ranking (see
+//!   [`crate::tantivy_search`])
+//!
+//! Note mutations report changes through [`SearchBackend::index_note`] and
+//! [`SearchBackend::remove_note`]. An external index can drift if an update
+//! fails or notes change outside the API; `backend --rebuild-search-index`
+//! rebuilds it from the database.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::config::SearchConfig;
+use crate::database::Database;
+use crate::errors::AppResult;
+use crate::search::{SearchFilters, SearchQuery};
+use crate::tantivy_search::TantivySearch;
+use crate::types::SearchResult;
+
+/// Search backend shared by the GraphQL schema
+pub type SharedSearchBackend = Arc<dyn SearchBackend>;
+
+/// 🗂️ Available search backends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackendKind {
+    #[default]
+    Postgres,
+    Tantivy,
+}
+
+impl std::str::FromStr for SearchBackendKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "postgres" => Ok(SearchBackendKind::Postgres),
+            "tantivy" => Ok(SearchBackendKind::Tantivy),
+            _ => Err("expected `postgres` or `tantivy`".to_string()),
+        }
+    }
+}
+
+/// 📄 The parts of a note a search index needs
+#[derive(Debug, Clone)]
+pub struct IndexedNote {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub folder_id: Option<Uuid>,
+    pub title: String,
+    pub content: String,
+    pub is_pinned: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 🔎 Full-text search over notes
+#[async_trait::async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Name used in logs (`postgres`, `tantivy`)
+    fn name(&self) -> &'static str;
+
+    /// One page of the user's notes matching `query`, best match first
+    ///
+    /// `raw_query` is the text `query` was parsed from.
+    #[allow(clippy::too_many_arguments)]
+    async fn search(
+        &self,
+        user_id: Uuid,
+        raw_query: &str,
+        query: &SearchQuery,
+        filters: &SearchFilters,
+        fuzzy_threshold: Option<f64>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<SearchResult>>;
+
+    /// Add or refresh a note after it was created or changed
+    async fn index_note(&self, note_id: Uuid) -> AppResult<()>;
+
+    /// Drop a deleted note from the index
+    async fn remove_note(&self, note_id: Uuid) -> AppResult<()>;
+
+    /// Drop every note of a deleted account from the index
+    async fn remove_user_notes(&self, user_id: Uuid) -> AppResult<()>;
+
+    /// Re-index every note, returning how many were indexed
+    async fn rebuild(&self) -> AppResult<usize>;
+}
+
+/// 🐘 PostgreSQL full-text search
+///
+/// The `search_vector` column is generated by PostgreSQL, so there is nothing
+/// to keep in sync.
+pub struct PostgresSearch {
+    db: Database,
+}
+
+impl PostgresSearch {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchBackend for PostgresSearch {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn search(
+        &self,
+        user_id: Uuid,
+        _raw_query: &str,
+        query: &SearchQuery,
+        filters: &SearchFilters,
+        fuzzy_threshold: Option<f64>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<SearchResult>> {
+        self.db
+            .search_notes(user_id, query, filters, fuzzy_threshold, limit, offset)
+            .await
+    }
+
+    async fn index_note(&self, _note_id: Uuid) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn remove_note(&self, _note_id: Uuid) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn remove_user_notes(&self, _user_id: Uuid) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn rebuild(&self) -> AppResult<usize> {
+        Ok(0)
+    }
+}
+
+/// 🏗️ Open the configured search backend
+///
+/// A newly created Tantivy index is filled from the database right away.
+pub async fn open_backend(config: &SearchConfig, db: Database) -> AppResult<SharedSearchBackend> {
+    match config.backend {
+        SearchBackendKind::Postgres => Ok(Arc::new(PostgresSearch::new(db))),
+        SearchBackendKind::Tantivy => {
+            tracing::warn!(
+                "The tantivy search backend stems every note in English; \
+                 user and note search languages only apply to the postgres backend"
+            );
+            let (backend, created) = TantivySearch::open(&config.index_dir, db)?;
+            if created {
+                let count = backend.rebuild().await?;
+                tracing::info!(count, dir = %config.index_dir.display(), "Built search index");
+            }
+            Ok(Arc::new(backend))
+        }
+    }
+}
+
+/// Keep the search index in step with a note change without failing the mutation
+///
+/// The note is already saved; a stale index entry is logged and can be fixed
+/// with `--rebuild-search-index`.
+pub async fn sync_note(backend: &dyn SearchBackend, note_id: Uuid, removed: bool) {
+    let result = if removed {
+        backend.remove_note(note_id).await
+    } else {
+        backend.index_note(note_id).await
+    };
+    if let Err(e) = result {
+        tracing::warn!(
+            error = %e,
+            %note_id,
+            backend = backend.name(),
+            "Failed to update search index"
+        );
+    }
+}
+
+/// Drop the notes of purged accounts from the search index, logging failures
+///
+/// A stale index entry can be fixed with `--rebuild-search-index`.
+pub async fn sync_purged_users(backend: &dyn SearchBackend, user_ids: &[Uuid]) {
+    for &user_id in user_ids {
+        if let Err(e) = backend.remove_user_notes(user_id).await {
+            tracing::warn!(
+                error = %e,
+                %user_id,
+                backend = backend.name(),
+                "Failed to remove purged account from search index"
+            );
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! # Graceful Shutdown
+//!
+//! Coordinates shutdown between the HTTP servers and background jobs.
+//!
+//! ## Sequence
+//!
+//! 1. SIGINT/SIGTERM arrives: readiness starts failing and [`Shutdown::trigger`] fires
+//! 2. The servers stop accepting connections and drain in-flight requests
+//! 3. Background jobs observe their [`ShutdownSignal`] and finish their current tick
+//! 4. Anything still running after the drain timeout is abandoned
+//! 5. The database pool is closed
+//!
+//! The API has no subscriptions, so there are no long-lived WebSocket
+//! connections to close; a future subscription transport should end its
+//! streams when its [`ShutdownSignal`] fires.
+
+use tokio::sync::watch;
+
+/// 🛑 Shutdown coordinator (cheap to clone)
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+}
+
+impl Shutdown {
+    /// Create a coordinator that has not been triggered yet
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self { sender }
+    }
+
+    /// Start shutting down; every subscriber is notified
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Subscribe to the shutdown notification
+    pub fn subscribe(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Receiving side of [`Shutdown`], handed to servers and background tasks
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    receiver: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    /// Resolve once shutdown has been triggered (immediately if it already was)
+    pub async fn recv(mut self) {
+        // An error means the coordinator is gone, which also means shutdown
+        let _ = self.receiver.wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Resolve when the process receives SIGINT (Ctrl+C) or SIGTERM
+pub async fn termination_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
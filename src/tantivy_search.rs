@@ -0,0 +1,791 @@
+//! # Tantivy Search Backend
+//!
+//! An embedded [Tantivy](https://github.com/quickwit-oss/tantivy) index on
+//! local disk, ranked with BM25. Only what search needs is indexed (title,
+//! content and the filterable fields); result pages are loaded from
+//! PostgreSQL, so notes are never served from a stale copy.
+//!
+//! Compared to the PostgreSQL backend:
+//!
+//! - Title and content are stemmed with Tantivy's English stemmer (`en_stem`),
+//!   whatever the user's or note's search language
+//! - Prefix terms and fuzzy matching use an unstemmed `words` field; fuzzy
+//!   matching allows one typo per word (two for words of 6+ characters, unless
+//!   `similarityThreshold` is at least 0.6)
+//!
+//! An index built with different fields by an older version is recreated and
+//! rebuilt from the database on startup.
+//!
+//! The index directory can only be written by one process at a time: stop the
+//! server before running `backend --rebuild-search-index`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{
+    AllQuery, BooleanQuery, FuzzyTermQuery, Occur, PhraseQuery, Query, RangeQuery, RegexQuery,
+    TermQuery,
+};
+use tantivy::schema::{
+    Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, FAST, INDEXED, STORED,
+    STRING,
+};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::tokenizer::TokenStream;
+use tantivy::{
+    DocAddress, Index, IndexReader, IndexWriter, ReloadPolicy, Searcher, TantivyDocument, Term,
+};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::search::{
+    hashtags, render_highlight, tokenize, SearchFilters, SearchQuery, HIGHLIGHT_START,
+    HIGHLIGHT_STOP,
+};
+use crate::search_backend::{IndexedNote, SearchBackend};
+use crate::types::SearchResult;
+
+/// Memory budget of the index writer
+const WRITER_HEAP_BYTES: usize = 50_000_000;
+/// Longest content snippet, in characters
+const SNIPPET_MAX_CHARS: usize = 200;
+/// Similarity threshold from which fuzzy matching allows a single typo per word
+const STRICT_FUZZY_THRESHOLD: f64 = 0.6;
+
+/// Fields of the note index
+#[derive(Clone, Copy)]
+struct NoteFields {
+    id: Field,
+    user_id: Field,
+    folder_id: Field,
+    title: Field,
+    content: Field,
+    /// Title and content, lowercased but not stemmed (prefix and fuzzy terms)
+    words: Field,
+    /// Lowercase hashtags of the content
+    tags: Field,
+    is_pinned: Field,
+    created_at: Field,
+    updated_at: Field,
+}
+
+impl NoteFields {
+    fn schema() -> (Schema, Self) {
+        let stemmed = TextOptions::default().set_stored().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("en_stem")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
+        let words = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("default")
+                .set_index_option(IndexRecordOption::WithFreqs),
+        );
+
+        let mut builder = Schema::builder();
+        let fields = NoteFields {
+            id: builder.add_text_field("id", STRING | STORED),
+            user_id: builder.add_text_field("user_id", STRING),
+            folder_id: builder.add_text_field("folder_id", STRING),
+            title: builder.add_text_field("title", stemmed.clone()),
+            content: builder.add_text_field("content", stemmed),
+            words: builder.add_text_field("words", words),
+            tags: builder.add_text_field("tags", STRING),
+            is_pinned: builder.add_bool_field("is_pinned", INDEXED),
+            created_at: builder.add_i64_field("created_at", INDEXED | FAST),
+            updated_at: builder.add_i64_field("updated_at", INDEXED | FAST),
+        };
+        (builder.build(), fields)
+    }
+
+    fn document(&self, note: &IndexedNote) -> TantivyDocument {
+        let mut doc = TantivyDocument::default();
+        doc.add_text(self.id, note.id.to_string());
+        doc.add_text(self.user_id, note.user_id.to_string());
+        if let Some(folder_id) = note.folder_id {
+            doc.add_text(self.folder_id, folder_id.to_string());
+        }
+        doc.add_text(self.title, &note.title);
+        doc.add_text(self.content, &note.content);
+        doc.add_text(self.words, format!("{} {}", note.title, note.content));
+        for tag in hashtags(&note.content) {
+            doc.add_text(self.tags, tag);
+        }
+        doc.add_bool(self.is_pinned, note.is_pinned);
+        doc.add_i64(self.created_at, note.created_at.timestamp_micros());
+        doc.add_i64(self.updated_at, note.updated_at.timestamp_micros());
+        doc
+    }
+}
+
+/// A matching note, before it is loaded from the database
+#[derive(Debug, Clone, PartialEq)]
+struct Hit {
+    note_id: Uuid,
+    score: f32,
+    exact_match: bool,
+    title_highlight: String,
+    snippets: Vec<String>,
+}
+
+/// Folder filters, resolved to folder IDs from the database
+#[derive(Debug, Clone, Default)]
+struct FolderFilter {
+    /// The note must be in one folder of each set
+    any_of: Vec<Vec<Uuid>>,
+    /// The note must not be in any of these folders
+    excluded: Vec<Uuid>,
+}
+
+/// The Tantivy index with its reader and (single) writer
+struct NoteIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    fields: NoteFields,
+}
+
+impl NoteIndex {
+    /// Open the index in `dir`, creating it when missing or outdated; `true` if it was created
+    fn open(dir: &Path) -> tantivy::Result<(Self, bool)> {
+        std::fs::create_dir_all(dir)?;
+        let (schema, fields) = NoteFields::schema();
+        let mut created = !Index::exists(&MmapDirectory::open(dir)?)?;
+        if !created && Index::open_in_dir(dir)?.schema() != schema {
+            tracing::info!(dir = %dir.display(), "Search index fields changed, recreating it");
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_file() {
+                    std::fs::remove_file(path)?;
+                }
+            }
+            created = true;
+        }
+        let index = Index::open_or_create(MmapDirectory::open(dir)?, schema)?;
+        Ok((Self::with_index(index, fields)?, created))
+    }
+
+    #[cfg(test)]
+    fn in_memory() -> tantivy::Result<Self> {
+        let (schema, fields) = NoteFields::schema();
+        Self::with_index(Index::create_in_ram(schema), fields)
+    }
+
+    fn with_index(index: Index, fields: NoteFields) -> tantivy::Result<Self> {
+        let writer = index.writer_with_num_threads(1, WRITER_HEAP_BYTES)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(Self {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            fields,
+        })
+    }
+
+    /// Apply changes under the writer lock, then commit and make them searchable
+    fn write(
+        &self,
+        change: impl FnOnce(&IndexWriter) -> tantivy::Result<()>,
+    ) -> tantivy::Result<()> {
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Drop a half-applied change rather than committing it with the next one
+        if let Err(e) = change(&writer) {
+            writer.rollback()?;
+            return Err(e);
+        }
+        writer.commit()?;
+        self.reader.reload()
+    }
+
+    fn upsert(&self, note: &IndexedNote) -> tantivy::Result<()> {
+        self.write(|writer| {
+            writer.delete_term(Term::from_field_text(self.fields.id, &note.id.to_string()));
+            writer.add_document(self.fields.document(note))?;
+            Ok(())
+        })
+    }
+
+    fn delete(&self, note_id: Uuid) -> tantivy::Result<()> {
+        self.write(|writer| {
+            writer.delete_term(Term::from_field_text(self.fields.id, &note_id.to_string()));
+            Ok(())
+        })
+    }
+
+    fn delete_user(&self, user_id: Uuid) -> tantivy::Result<()> {
+        self.write(|writer| {
+            writer.delete_term(Term::from_field_text(
+                self.fields.user_id,
+                &user_id.to_string(),
+            ));
+            Ok(())
+        })
+    }
+
+    fn replace_all(&self, notes: &[IndexedNote]) -> tantivy::Result<()> {
+        self.write(|writer| {
+            writer.delete_all_documents()?;
+            for note in notes {
+                writer.add_document(self.fields.document(note))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// One page of matching notes
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        user_id: Uuid,
+        raw_query: &str,
+        query: &SearchQuery,
+        folders: &FolderFilter,
+        filters: &SearchFilters,
+        fuzzy_threshold: Option<f64>,
+        limit: usize,
+        offset: usize,
+    ) -> tantivy::Result<Vec<Hit>> {
+        let text_query = self.text_query(raw_query)?;
+        let matching: Box<dyn Query> = match fuzzy_threshold {
+            Some(threshold) => match self.fuzzy_query(&query.fuzzy_text, threshold)? {
+                Some(fuzzy) => Box::new(BooleanQuery::new(vec![
+                    (Occur::Should, text_query.box_clone()),
+                    (Occur::Should, fuzzy),
+                ])),
+                None => text_query.box_clone(),
+            },
+            None => text_query.box_clone(),
+        };
+
+        let mut clauses = vec![
+            (Occur::Must, matching),
+            (Occur::Must, self.term(self.fields.user_id, user_id)),
+        ];
+        for folder_ids in &folders.any_of {
+            clauses.push((
+                Occur::Must,
+                Box::new(BooleanQuery::new(
+                    folder_ids
+                        .iter()
+                        .map(|id| (Occur::Should, self.term(self.fields.folder_id, *id)))
+                        .collect(),
+                )),
+            ));
+        }
+        for id in &folders.excluded {
+            clauses.push((Occur::MustNot, self.term(self.fields.folder_id, *id)));
+        }
+        for (occur, tags) in [
+            (Occur::Must, &filters.tags),
+            (Occur::MustNot, &filters.excluded_tags),
+        ] {
+            for tag in tags {
+                clauses.push((
+                    occur,
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(self.fields.tags, tag),
+                        IndexRecordOption::Basic,
+                    )),
+                ));
+            }
+        }
+        if let Some(is_pinned) = filters.is_pinned {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_bool(self.fields.is_pinned, is_pinned),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        for (name, after, before) in [
+            ("created_at", filters.created_after, filters.created_before),
+            ("updated_at", filters.updated_after, filters.updated_before),
+        ] {
+            if after.is_some() || before.is_some() {
+                clauses.push((
+                    Occur::Must,
+                    Box::new(RangeQuery::new_i64_bounds(
+                        name.to_string(),
+                        after.map_or(Bound::Unbounded, |at| {
+                            Bound::Included(at.timestamp_micros())
+                        }),
+                        before.map_or(Bound::Unbounded, |at| {
+                            Bound::Excluded(at.timestamp_micros())
+                        }),
+                    )),
+                ));
+            }
+        }
+        let search_query = BooleanQuery::new(clauses);
+
+        let searcher = self.reader.searcher();
+        let top_docs = searcher.search(
+            &search_query,
+            &TopDocs::with_limit(limit).and_offset(offset),
+        )?;
+        top_docs
+            .into_iter()
+            .map(|(score, address)| self.hit(&searcher, &*text_query, score, address))
+            .collect()
+    }
+
+    fn hit(
+        &self,
+        searcher: &Searcher,
+        text_query: &dyn Query,
+        score: f32,
+        address: DocAddress,
+    ) -> tantivy::Result<Hit> {
+        let doc: TantivyDocument = searcher.doc(address)?;
+        let text = |field| {
+            doc.get_first(field)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let note_id = Uuid::parse_str(&text(self.fields.id))
+            .map_err(|e| tantivy::TantivyError::InternalError(e.to_string()))?;
+        let title = text(self.fields.title);
+        let content = text(self.fields.content);
+
+        let mut generator = SnippetGenerator::create(searcher, text_query, self.fields.content)?;
+        generator.set_max_num_chars(SNIPPET_MAX_CHARS);
+        let snippet = generator.snippet(&content);
+        let snippet = if snippet.is_empty() {
+            // Fuzzy-only match: show the start of the note, like `ts_headline` does
+            render_highlight(&content.chars().take(SNIPPET_MAX_CHARS).collect::<String>())
+        } else {
+            render_highlight(&mark(snippet.fragment(), snippet.highlighted()))
+        };
+
+        Ok(Hit {
+            note_id,
+            score,
+            exact_match: text_query.explain(searcher, address).is_ok(),
+            title_highlight: self.highlight_title(&title, text_query)?,
+            snippets: vec![snippet]
+                .into_iter()
+                .filter(|s| !s.is_empty())
+                .collect(),
+        })
+    }
+
+    /// Highlight every title token that matches a term of the query
+    fn highlight_title(&self, title: &str, text_query: &dyn Query) -> tantivy::Result<String> {
+        let mut terms = BTreeSet::new();
+        text_query.query_terms(&mut |term, _| {
+            if term.field() == self.fields.title {
+                if let Some(text) = term.value().as_str() {
+                    terms.insert(text.to_string());
+                }
+            }
+        });
+
+        let mut analyzer = self.index.tokenizer_for_field(self.fields.title)?;
+        let mut stream = analyzer.token_stream(title);
+        let mut ranges = Vec::new();
+        while stream.advance() {
+            let token = stream.token();
+            if terms.contains(&token.text) {
+                ranges.push(token.offset_from..token.offset_to);
+            }
+        }
+        Ok(render_highlight(&mark(title, &ranges)))
+    }
+
+    /// Compile the websearch-style query (see [`crate::search`]) to a Tantivy query
+    fn text_query(&self, raw_query: &str) -> tantivy::Result<Box<dyn Query>> {
+        let mut groups: Vec<Vec<Box<dyn Query>>> = Vec::new();
+        let mut excluded = Vec::new();
+        let mut alternative = false;
+
+        for token in tokenize(raw_query) {
+            if token.eq_ignore_ascii_case("or") {
+                alternative = !groups.is_empty();
+                continue;
+            }
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, token),
+            };
+            let Some(clause) = self.clause(token)? else {
+                continue;
+            };
+
+            if negated {
+                excluded.push((Occur::MustNot, clause));
+            } else if alternative {
+                groups.last_mut().expect("checked above").push(clause);
+            } else {
+                groups.push(vec![clause]);
+            }
+            alternative = false;
+        }
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = groups
+            .into_iter()
+            .map(|group| (Occur::Must, any_of(group)))
+            .collect();
+        if clauses.is_empty() {
+            // Only exclusions: everything else matches, as with `websearch_to_tsquery`
+            clauses.push((Occur::Must, Box::new(AllQuery)));
+        }
+        clauses.extend(excluded);
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
+
+    /// A word, `"phrase"` or `prefix*` matched in the title or the content
+    fn clause(&self, token: &str) -> tantivy::Result<Option<Box<dyn Query>>> {
+        if let Some(stem) = token.strip_suffix('*') {
+            if !stem.is_empty() && stem.chars().all(char::is_alphanumeric) {
+                let pattern = format!("{}.*", stem.to_lowercase());
+                return Ok(Some(Box::new(RegexQuery::from_pattern(
+                    &pattern,
+                    self.fields.words,
+                )?)));
+            }
+        }
+
+        let text = token.trim_matches(|c| c == '"' || c == '*');
+        let mut fields = Vec::new();
+        for field in [self.fields.title, self.fields.content] {
+            let terms = self.analyze(field, text)?;
+            let query: Box<dyn Query> = match terms.len() {
+                0 => return Ok(None),
+                1 => Box::new(TermQuery::new(
+                    terms[0].1.clone(),
+                    IndexRecordOption::WithFreqs,
+                )),
+                _ => Box::new(PhraseQuery::new_with_offset(terms)),
+            };
+            fields.push(query);
+        }
+        Ok(Some(any_of(fields)))
+    }
+
+    /// Words similar to every word of `text`, allowing a typo or two each
+    fn fuzzy_query(&self, text: &str, threshold: f64) -> tantivy::Result<Option<Box<dyn Query>>> {
+        let clauses = self
+            .analyze(self.fields.words, text)?
+            .into_iter()
+            .map(|(_, term)| {
+                let length = term.value().as_str().map_or(0, |word| word.chars().count());
+                let distance = if length >= 6 && threshold < STRICT_FUZZY_THRESHOLD {
+                    2
+                } else {
+                    1
+                };
+                let query: Box<dyn Query> = Box::new(FuzzyTermQuery::new(term, distance, true));
+                (Occur::Must, query)
+            })
+            .collect::<Vec<_>>();
+        Ok((!clauses.is_empty()).then(|| Box::new(BooleanQuery::new(clauses)) as Box<dyn Query>))
+    }
+
+    /// Tokens of `text` as `field` indexes them, with their positions
+    fn analyze(&self, field: Field, text: &str) -> tantivy::Result<Vec<(usize, Term)>> {
+        let mut analyzer = self.index.tokenizer_for_field(field)?;
+        let mut stream = analyzer.token_stream(text);
+        let mut terms = Vec::new();
+        while stream.advance() {
+            let token = stream.token();
+            terms.push((token.position, Term::from_field_text(field, &token.text)));
+        }
+        Ok(terms)
+    }
+
+    fn term(&self, field: Field, id: Uuid) -> Box<dyn Query> {
+        Box::new(TermQuery::new(
+            Term::from_field_text(field, &id.to_string()),
+            IndexRecordOption::Basic,
+        ))
+    }
+}
+
+/// Match any of `queries`
+fn any_of(mut queries: Vec<Box<dyn Query>>) -> Box<dyn Query> {
+    if queries.len() == 1 {
+        return queries.remove(0);
+    }
+    Box::new(BooleanQuery::new(
+        queries
+            .into_iter()
+            .map(|query| (Occur::Should, query))
+            .collect(),
+    ))
+}
+
+/// Wrap the highlighted byte ranges of `text` in the highlight markers
+fn mark(text: &str, ranges: &[std::ops::Range<usize>]) -> String {
+    let mut marked = String::with_capacity(text.len() + ranges.len() * 2);
+    let mut position = 0;
+    for range in ranges {
+        marked.push_str(&text[position..range.start]);
+        marked.push(HIGHLIGHT_START);
+        marked.push_str(&text[range.clone()]);
+        marked.push(HIGHLIGHT_STOP);
+        position = range.end;
+    }
+    marked.push_str(&text[position..]);
+    marked
+}
+
+fn index_error(context: &'static str) -> impl FnOnce(tantivy::TantivyError) -> AppError {
+    move |e| {
+        tracing::error!(error = %e, "{}", context);
+        AppError::SearchIndexError {
+            message: format!("{}: {}", context, e),
+        }
+    }
+}
+
+/// 🔍 Search backend backed by a Tantivy index on local disk
+pub struct TantivySearch {
+    index: Arc<NoteIndex>,
+    db: Database,
+}
+
+impl TantivySearch {
+    /// Open (or create) the index in `dir`; `true` when it was just created and is empty
+    pub fn open(dir: &Path, db: Database) -> AppResult<(Self, bool)> {
+        let (index, created) =
+            NoteIndex::open(dir).map_err(index_error("Failed to open search index"))?;
+        Ok((
+            Self {
+                index: Arc::new(index),
+                db,
+            },
+            created,
+        ))
+    }
+
+    /// Run blocking index work off the async runtime
+    async fn blocking<T: Send + 'static>(
+        &self,
+        context: &'static str,
+        work: impl FnOnce(&NoteIndex) -> tantivy::Result<T> + Send + 'static,
+    ) -> AppResult<T> {
+        let index = self.index.clone();
+        tokio::task::spawn_blocking(move || work(&index))
+            .await
+            .map_err(|e| AppError::SearchIndexError {
+                message: format!("{}: {}", context, e),
+            })?
+            .map_err(index_error(context))
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchBackend for TantivySearch {
+    fn name(&self) -> &'static str {
+        "tantivy"
+    }
+
+    async fn search(
+        &self,
+        user_id: Uuid,
+        raw_query: &str,
+        query: &SearchQuery,
+        filters: &SearchFilters,
+        fuzzy_threshold: Option<f64>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<SearchResult>> {
+        let mut folders = FolderFilter::default();
+        if let Some(folder_id) = filters.folder_id {
+            folders.any_of.push(
+                self.db
+                    .folder_scope(user_id, folder_id, filters.include_subfolders)
+                    .await?,
+            );
+        }
+        if !filters.folder_names.is_empty() {
+            folders.any_of.push(
+                self.db
+                    .folder_ids_by_names(user_id, &filters.folder_names)
+                    .await?,
+            );
+        }
+        if folders.any_of.iter().any(|ids| ids.is_empty()) {
+            return Ok(Vec::new());
+        }
+        if !filters.excluded_folder_names.is_empty() {
+            folders.excluded = self
+                .db
+                .folder_ids_by_names(user_id, &filters.excluded_folder_names)
+                .await?;
+        }
+
+        let (raw_query, query, filters) = (raw_query.to_string(), query.clone(), filters.clone());
+        let hits = self
+            .blocking("Failed to search notes", move |index| {
+                index.search(
+                    user_id,
+                    &raw_query,
+                    &query,
+                    &folders,
+                    &filters,
+                    fuzzy_threshold,
+                    usize::try_from(limit).unwrap_or_default(),
+                    usize::try_from(offset).unwrap_or_default(),
+                )
+            })
+            .await?;
+
+        // Notes deleted since they were indexed are skipped
+        let ids = hits.iter().map(|hit| hit.note_id).collect::<Vec<_>>();
+        let mut notes = self
+            .db
+            .get_user_notes_by_ids(user_id, &ids)
+            .await?
+            .into_iter()
+            .map(|note| (note.id.clone(), note))
+            .collect::<HashMap<_, _>>();
+
+        Ok(hits
+            .into_iter()
+            .filter_map(|hit| {
+                let note = notes.remove(&hit.note_id.to_string())?;
+                Some(SearchResult {
+                    note,
+                    score: f64::from(hit.score),
+                    exact_match: hit.exact_match,
+                    title_highlight: hit.title_highlight,
+                    snippets: hit.snippets,
+                })
+            })
+            .collect())
+    }
+
+    async fn index_note(&self, note_id: Uuid) -> AppResult<()> {
+        match self.db.notes_for_index(Some(note_id)).await?.pop() {
+            Some(note) => {
+                self.blocking("Failed to index note", move |index| index.upsert(&note))
+                    .await
+            }
+            None => self.remove_note(note_id).await,
+        }
+    }
+
+    async fn remove_note(&self, note_id: Uuid) -> AppResult<()> {
+        self.blocking("Failed to remove note from index", move |index| {
+            index.delete(note_id)
+        })
+        .await
+    }
+
+    async fn remove_user_notes(&self, user_id: Uuid) -> AppResult<()> {
+        self.blocking("Failed to remove account from index", move |index| {
+            index.delete_user(user_id)
+        })
+        .await
+    }
+
+    async fn rebuild(&self) -> AppResult<usize> {
+        let notes = self.db.notes_for_index(None).await?;
+        let count = notes.len();
+        self.blocking("Failed to rebuild search index", move |index| {
+            index.replace_all(&notes)
+        })
+        .await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn note(user_id: Uuid, title: &str, content: &str) -> IndexedNote {
+        IndexedNote {
+            id: Uuid::new_v4(),
+            user_id,
+            folder_id: None,
+            title: title.to_string(),
+            content: content.to_string(),
+            is_pinned: false,
+            created_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    fn search(index: &NoteIndex, user_id: Uuid, raw: &str, fuzzy: Option<f64>) -> Vec<Hit> {
+        index
+            .search(
+                user_id,
+                raw,
+                &SearchQuery::parse(raw),
+                &FolderFilter::default(),
+                &SearchFilters::default(),
+                fuzzy,
+                10,
+                0,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_search_is_scoped_stemmed_and_highlighted() {
+        let index = NoteIndex::in_memory().unwrap();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let rust = note(alice, "Rust <ownership>", "Borrowing rules for programs");
+        let java = note(alice, "Java", "Programming with classes");
+        index
+            .replace_all(&[rust.clone(), java.clone(), note(bob, "Rust", "Programs")])
+            .unwrap();
+
+        let hits = search(&index, alice, "programming -java", None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].note_id, rust.id);
+        assert!(hits[0].exact_match);
+        assert_eq!(
+            hits[0].snippets,
+            vec!["Borrowing rules for <mark>programs</mark>"]
+        );
+
+        let hits = search(&index, alice, "\"rust ownership\" or classes", None);
+        assert_eq!(hits.len(), 2);
+        let rust_hit = hits.iter().find(|hit| hit.note_id == rust.id).unwrap();
+        assert_eq!(
+            rust_hit.title_highlight,
+            "<mark>Rust</mark> &lt;<mark>ownership</mark>&gt;"
+        );
+
+        index.delete(java.id).unwrap();
+        assert!(search(&index, alice, "class*", None).is_empty());
+        assert_eq!(search(&index, alice, "borrow*", None).len(), 1);
+
+        index.delete_user(alice).unwrap();
+        assert!(search(&index, alice, "borrow*", None).is_empty());
+        assert_eq!(search(&index, bob, "rust", None).len(), 1);
+    }
+
+    #[test]
+    fn test_fuzzy_matches_typos() {
+        let index = NoteIndex::in_memory().unwrap();
+        let user_id = Uuid::new_v4();
+        index
+            .upsert(&note(user_id, "PostgreSQL tuning", "Indexes and vacuum"))
+            .unwrap();
+
+        assert!(search(&index, user_id, "postgressql", None).is_empty());
+        let hits = search(&index, user_id, "postgressql", Some(0.3));
+        assert_eq!(hits.len(), 1);
+        assert!(!hits[0].exact_match);
+        assert!(search(&index, user_id, "postgressql", Some(0.8)).len() == 1);
+        assert!(search(&index, user_id, "postgrez", Some(0.8)).is_empty());
+    }
+}
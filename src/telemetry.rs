@@ -0,0 +1,80 @@
+//! # Structured Logging and Tracing
+//!
+//! Sets up the `tracing` subscriber and provides helpers for logging errors.
+//!
+//! The output format comes from `logging.format` in the
+//! [configuration](crate::config); filtering uses `RUST_LOG` (defaults to `info`).
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use tracing_subscriber::{fmt as subscriber_fmt, EnvFilter};
+
+/// Default filter when `RUST_LOG` is not set
+const DEFAULT_LOG_FILTER: &str = "info,sqlx=warn";
+
+/// Log output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable multi-line output
+    #[default]
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected `pretty` or `json`".to_string()),
+        }
+    }
+}
+
+/// 📜 Install the global tracing subscriber
+pub fn init_tracing(format: LogFormat) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+
+    let builder = subscriber_fmt().with_env_filter(filter).with_target(true);
+
+    match format {
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+        LogFormat::Pretty => builder.pretty().init(),
+    }
+}
+
+/// Display adapter that renders an error followed by its full `source()` chain
+///
+/// ```ignore
+/// tracing::error!(error = %ErrorChain(&e), "Query failed");
+/// // error="pool timed out: caused by: connection refused"
+/// ```
+pub struct ErrorChain<'a>(pub &'a dyn Error);
+
+impl fmt::Display for ErrorChain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+
+        let mut source = self.0.source();
+        while let Some(cause) = source {
+            write!(f, ": caused by: {}", cause)?;
+            source = cause.source();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! # Note Templates
+//!
+//! Templates are note skeletons with `{{placeholder}}` markers in the title
+//! and content. Built-in placeholders are filled in from the clock and the
+//! current user:
+//!
+//! | Placeholder | Value |
+//! |-------------|-------|
+//! | `{{date}}` | `2025-10-09` |
+//! | `{{time}}` | `14:30` |
+//! | `{{datetime}}` | `2025-10-09 14:30` |
+//! | `{{weekday}}` | `Thursday` |
+//! | `{{user.full_name}}` | the user's full name (empty when not set) |
+//! | `{{user.email}}` | the user's email address |
+//!
+//! Any other placeholder is a prompt: its value must be passed in the
+//! `variables` of `createNoteFromTemplate`. Times are in UTC. Text between
+//! braces that is not a valid name (letters, digits, `_` and `.`) is left as
+//! it is.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+/// Placeholders filled in without being asked for
+pub const BUILTIN_PLACEHOLDERS: [&str; 6] = [
+    "date",
+    "time",
+    "datetime",
+    "weekday",
+    "user.full_name",
+    "user.email",
+];
+
+/// Values available while rendering a template
+#[derive(Debug, Clone)]
+pub struct TemplateValues {
+    pub now: DateTime<Utc>,
+    pub full_name: Option<String>,
+    pub email: String,
+    /// Answers to the template's prompts
+    pub variables: HashMap<String, String>,
+}
+
+impl TemplateValues {
+    fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "date" => self.now.format("%Y-%m-%d").to_string(),
+            "time" => self.now.format("%H:%M").to_string(),
+            "datetime" => self.now.format("%Y-%m-%d %H:%M").to_string(),
+            "weekday" => self.now.format("%A").to_string(),
+            "user.full_name" => self.full_name.clone().unwrap_or_default(),
+            "user.email" => self.email.clone(),
+            _ => return self.variables.get(name).cloned(),
+        };
+        Some(value)
+    }
+}
+
+/// A placeholder in `text`: byte range of the whole `{{ ... }}` and the name
+fn next_placeholder(text: &str) -> Option<(usize, usize, &str)> {
+    let mut from = 0;
+    while let Some(open) = text[from..].find("{{").map(|i| from + i) {
+        let close = text[open + 2..].find("}}").map(|i| open + 2 + i)?;
+        let name = text[open + 2..close].trim();
+        if is_placeholder_name(name) {
+            return Some((open, close + 2, name));
+        }
+        from = open + 2;
+    }
+    None
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Names of the placeholders in `text`, in order of first appearance
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some((_, end, name)) = next_placeholder(rest) {
+        if !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+        rest = &rest[end..];
+    }
+    names
+}
+
+/// Placeholders of a template that need a value from the caller
+pub fn prompts(title: Option<&str>, content: &str) -> Vec<String> {
+    let mut names = placeholders(title.unwrap_or_default());
+    for name in placeholders(content) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names.retain(|name| !BUILTIN_PLACEHOLDERS.contains(&name.as_str()));
+    names
+}
+
+/// Replace every placeholder in `text`; `Err` names the first one without a value
+pub fn render(text: &str, values: &TemplateValues) -> Result<String, String> {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end, name)) = next_placeholder(rest) {
+        let value = values.get(name).ok_or_else(|| name.to_string())?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&value);
+        rest = &rest[end..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_fills_builtins_and_prompts() {
+        let values = TemplateValues {
+            now: Utc.with_ymd_and_hms(2025, 10, 9, 14, 30, 0).unwrap(),
+            full_name: Some("Ada Lovelace".to_string()),
+            email: "ada@example.com".to_string(),
+            variables: HashMap::from([("project".to_string(), "Engine".to_string())]),
+        };
+        let template = "# {{ project }} standup {{date}} ({{weekday}})\nBy {{user.full_name}}\n{{ not a name }} {{project}}";
+
+        assert_eq!(
+            render(template, &values).unwrap(),
+            "# Engine standup 2025-10-09 (Thursday)\nBy Ada Lovelace\n{{ not a name }} Engine"
+        );
+        assert_eq!(
+            prompts(Some("{{date}} {{topic}}"), template),
+            ["topic", "project"]
+        );
+        assert_eq!(render("{{topic}}", &values), Err("topic".to_string()));
+        assert_eq!(render("{{unclosed", &values).unwrap(), "{{unclosed");
+    }
+}
@@ -2,7 +2,18 @@
 //!
 //! Enhanced types with folder system and advanced features
 
-use async_graphql::{InputObject, SimpleObject};
+use async_graphql::{Enum, InputObject, SimpleObject, Union};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::audit::{AuditAction, AuditTargetType};
+
+/// Longest accepted note title
+const TITLE_MAX_LENGTH: u64 = 200;
+/// Longest accepted folder name (matches the `folders.name` check constraint)
+const FOLDER_NAME_MAX_LENGTH: u64 = 100;
+/// Longest accepted search query
+pub const SEARCH_QUERY_MAX_LENGTH: u64 = 500;
 
 /// Note type for GraphQL responses
 #[derive(SimpleObject, Clone)]
@@ -29,6 +40,8 @@ pub struct Note {
     pub word_count: i32,
     /// The folder containing the note, if any
     pub folder: Option<Folder>,
+    /// Search language override; `null` means the owner's language is used
+    pub language: Option<String>,
 }
 
 /// 📁 Folder type for organization
@@ -63,51 +76,64 @@ pub struct Folder {
     pub subfolders: Vec<Folder>,
 }
 
-/// 📊 Folder statistics
-#[derive(SimpleObject)]
-pub struct FolderStats {
-    /// Total number of notes in the folder
-    pub total_notes: i32,
-    /// Number of pinned notes in the folder
-    pub pinned_notes: i32,
-    /// Number of recent notes (from last 7 days)
-    pub recent_notes: i32,
-    /// Total number of words in the folder
-    pub total_words: i32,
-    /// Last activity timestamp (RFC3339 format, optional)
-    pub last_activity: Option<String>,
-}
-
 /// Input for creating notes with folder support
-#[derive(InputObject)]
+#[derive(InputObject, Validate)]
 pub struct NoteInput {
     /// Optional title (if not provided, will be auto-generated)
+    #[validate(length(
+        max = "TITLE_MAX_LENGTH",
+        message = "Title must be at most 200 characters"
+    ))]
     pub title: Option<String>,
     /// Note content (required)
+    #[validate(custom(function = "not_blank", message = "Content cannot be empty"))]
     pub content: String,
     /// Optional folder ID to place the note in
+    #[validate(custom(function = "uuid_string"))]
     pub folder_id: Option<String>,
     /// Optional flag to pin the note
     pub is_pinned: Option<bool>,
+    /// Optional search language override, one of `searchLanguages`
+    #[validate(length(min = 1, max = 63, message = "Language cannot be empty"))]
+    pub language: Option<String>,
 }
 
 /// Input for updating notes
-#[derive(InputObject)]
+#[derive(InputObject, Validate)]
 pub struct UpdateNoteInput {
     /// Optional new title
+    #[validate(
+        custom(function = "not_blank", message = "Title cannot be empty"),
+        length(
+            max = "TITLE_MAX_LENGTH",
+            message = "Title must be at most 200 characters"
+        )
+    )]
     pub title: Option<String>,
     /// Optional new content
+    #[validate(custom(function = "not_blank", message = "Content cannot be empty"))]
     pub content: Option<String>,
     /// Optional new folder ID
+    #[validate(custom(function = "uuid_string"))]
     pub folder_id: Option<String>,
     /// Optional flag to pin or unpin the note
     pub is_pinned: Option<bool>,
+    /// Optional search language override; an empty string reverts to the owner's language
+    #[validate(length(max = 63, message = "Language must be at most 63 characters"))]
+    pub language: Option<String>,
 }
 
 /// 📁 Input for creating folders
-#[derive(InputObject)]
+#[derive(InputObject, Validate)]
 pub struct CreateFolderInput {
     /// Folder name (required)
+    #[validate(
+        custom(function = "not_blank", message = "Folder name cannot be empty"),
+        length(
+            max = "FOLDER_NAME_MAX_LENGTH",
+            message = "Folder name must be at most 100 characters"
+        )
+    )]
     pub name: String,
     /// Optional folder description
     pub description: Option<String>,
@@ -116,15 +142,23 @@ pub struct CreateFolderInput {
     /// Optional folder icon
     pub icon: Option<String>,
     /// Optional parent folder ID (for subfolders)
+    #[validate(custom(function = "uuid_string"))]
     pub parent_id: Option<String>,
     /// Optional position of the folder in the list
     pub position: Option<i32>,
 }
 
 /// 📁 Input for updating folders
-#[derive(InputObject)]
+#[derive(InputObject, Validate)]
 pub struct UpdateFolderInput {
     /// Optional new name
+    #[validate(
+        custom(function = "not_blank", message = "Folder name cannot be empty"),
+        length(
+            max = "FOLDER_NAME_MAX_LENGTH",
+            message = "Folder name must be at most 100 characters"
+        )
+    )]
     pub name: Option<String>,
     /// Optional new description
     pub description: Option<String>,
@@ -133,35 +167,442 @@ pub struct UpdateFolderInput {
     /// Optional new icon
     pub icon: Option<String>,
     /// Optional new parent folder ID
+    #[validate(custom(function = "uuid_string"))]
     pub parent_id: Option<String>,
     /// Optional new position
     pub position: Option<i32>,
 }
 
 /// 🔄 Input for moving folders/notes
-#[derive(InputObject)]
+#[derive(InputObject, Validate)]
 pub struct MoveToFolderInput {
     /// Optional target folder ID (None means root level)
+    #[validate(custom(function = "uuid_string"))]
     pub target_folder_id: Option<String>,
     /// Optional new position
     pub position: Option<i32>,
 }
 
-/// User type for GraphQL responses
-#[derive(SimpleObject)]
-pub struct User {
+/// 🧰 Filters for the `notes` query; every given condition must match
+#[derive(InputObject, Validate, Default)]
+pub struct NoteFilter {
+    /// Only notes in this folder
+    #[validate(custom(function = "uuid_string"))]
+    pub folder_id: Option<String>,
+    /// Also include notes in subfolders of `folderId` (defaults to false)
+    pub include_subfolders: Option<bool>,
+    /// Only pinned (true) or unpinned (false) notes
+    pub is_pinned: Option<bool>,
+    /// Notes created at or after this time (RFC3339 format)
+    pub created_after: Option<String>,
+    /// Notes created before this time (RFC3339 format)
+    pub created_before: Option<String>,
+    /// Notes updated at or after this time (RFC3339 format)
+    pub updated_after: Option<String>,
+    /// Notes updated before this time (RFC3339 format)
+    pub updated_before: Option<String>,
+    /// Notes with at least this many words
+    #[validate(range(min = 0, message = "Word count cannot be negative"))]
+    pub min_word_count: Option<i32>,
+    /// Notes with at most this many words
+    #[validate(range(min = 0, message = "Word count cannot be negative"))]
+    pub max_word_count: Option<i32>,
+    /// Notes with (true) or without (false) attachments; notes cannot have
+    /// attachments yet, so `true` matches no note
+    pub has_attachments: Option<bool>,
+}
+
+/// ↕️ Field to sort the `notes` query by
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NoteOrderField {
+    Title,
+    CreatedAt,
+    #[default]
+    UpdatedAt,
+    WordCount,
+    ViewCount,
+}
+
+/// ↕️ Sort direction
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// ↕️ Sort order for the `notes` query
+#[derive(InputObject, Clone, Copy, Debug, Default)]
+pub struct NoteOrderBy {
+    /// Field to sort by (defaults to UPDATED_AT)
+    #[graphql(default)]
+    pub field: NoteOrderField,
+    /// Direction (defaults to ASC for TITLE, DESC otherwise)
+    pub direction: Option<SortDirection>,
+}
+
+/// 🔎 Input for searching the current user's notes
+#[derive(InputObject, Validate, Default)]
+pub struct SearchInput {
+    /// Search text: words, `"exact phrases"`, `or`, `-excluded` and `prefix*` terms
+    /// (required unless `searchNotes(q:)` is used)
+    #[validate(
+        custom(function = "not_blank", message = "Search query cannot be empty"),
+        length(
+            max = "SEARCH_QUERY_MAX_LENGTH",
+            message = "Search query must be at most 500 characters"
+        )
+    )]
+    pub query: Option<String>,
+    /// Only search notes in this folder
+    #[validate(custom(function = "uuid_string"))]
+    pub folder_id: Option<String>,
+    /// Also search the subfolders of `folderId` (defaults to false)
+    pub include_subfolders: Option<bool>,
+    /// Only pinned (true) or unpinned (false) notes
+    pub is_pinned: Option<bool>,
+    /// Notes created at or after this time (RFC3339 format)
+    pub created_after: Option<String>,
+    /// Notes created before this time (RFC3339 format)
+    pub created_before: Option<String>,
+    /// Notes updated at or after this time (RFC3339 format)
+    pub updated_after: Option<String>,
+    /// Notes updated before this time (RFC3339 format)
+    pub updated_before: Option<String>,
+    /// Also match similar words and typos by trigram similarity (defaults to false)
+    pub fuzzy: Option<bool>,
+    /// Minimum trigram word similarity for fuzzy matches (0.1-1.0, defaults to 0.3)
+    #[validate(range(
+        min = 0.1,
+        max = 1.0,
+        message = "Similarity threshold must be between 0.1 and 1.0"
+    ))]
+    pub similarity_threshold: Option<f64>,
+    /// Maximum number of results (1-100, defaults to 20)
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<i32>,
+    /// Number of results to skip (defaults to 0)
+    #[validate(range(min = 0, message = "Offset cannot be negative"))]
+    pub offset: Option<i32>,
+}
+
+/// 🔎 Search results, with a suggestion when the query found nothing
+#[derive(SimpleObject, Clone)]
+pub struct SearchResponse {
+    /// Matching notes, most relevant first
+    pub results: Vec<SearchResult>,
+    /// Corrected query to offer when no note matched the full-text query
+    pub did_you_mean: Option<String>,
+}
+
+/// 🎯 A search hit with its relevance score and highlighted matches
+#[derive(SimpleObject, Clone)]
+pub struct SearchResult {
+    /// The matching note
+    pub note: Note,
+    /// Relevance, higher is better: `ts_rank` plus trigram similarity in fuzzy mode
+    pub score: f64,
+    /// Whether the note matched the full-text query (false for fuzzy-only matches)
+    pub exact_match: bool,
+    /// HTML-escaped title with matches wrapped in `<mark>`
+    pub title_highlight: String,
+    /// HTML-escaped content fragments with matches wrapped in `<mark>`
+    pub snippets: Vec<String>,
+}
+
+/// 🔖 Smart folder: a saved search whose notes are found each time it is opened
+#[derive(SimpleObject, Clone)]
+#[graphql(complex)]
+pub struct SmartFolder {
     /// Unique identifier (UUID as string)
     pub id: String,
-    /// User email address
-    pub email: String,
-    /// Full name of the user, if provided
-    pub full_name: Option<String>,
-    /// Account creation timestamp (RFC3339 format)
-    #[graphql(name = "createdAt")]
+    /// Smart folder name
+    pub name: String,
+    /// Saved query, in the `searchNotes(q:)` query language
+    pub query: String,
+    /// Only search this folder, if set
+    pub folder_id: Option<String>,
+    /// Also search the subfolders of `folderId`
+    pub include_subfolders: bool,
+    /// Also match similar words and typos
+    pub fuzzy: bool,
+    /// Smart folder color (hex code)
+    pub color: String,
+    /// Smart folder icon (font icon name)
+    pub icon: String,
+    /// Position of the smart folder in the list
+    pub position: i32,
+    /// Creation timestamp (RFC3339 format)
     pub created_at: String,
     /// Last update timestamp (RFC3339 format)
-    #[graphql(name = "updatedAt")]
     pub updated_at: String,
-    /// Is the user active?
-    pub is_active: bool,
+    /// Owner of the saved search, whose notes are searched
+    #[graphql(skip)]
+    pub user_id: Uuid,
+}
+
+/// 🗂️ A regular folder or a smart folder
+#[derive(Union, Clone)]
+pub enum AnyFolder {
+    Folder(Folder),
+    SmartFolder(SmartFolder),
+}
+
+/// 🔖 Input for saving a search as a smart folder
+#[derive(InputObject, Validate)]
+pub struct CreateSavedSearchInput {
+    /// Smart folder name (required)
+    #[validate(
+        custom(function = "not_blank", message = "Name cannot be empty"),
+        length(
+            max = "FOLDER_NAME_MAX_LENGTH",
+            message = "Name must be at most 100 characters"
+        )
+    )]
+    pub name: String,
+    /// Query in the `searchNotes(q:)` query language (required)
+    #[validate(
+        custom(function = "not_blank", message = "Search query cannot be empty"),
+        length(
+            max = "SEARCH_QUERY_MAX_LENGTH",
+            message = "Search query must be at most 500 characters"
+        )
+    )]
+    pub query: String,
+    /// Only search this folder
+    #[validate(custom(function = "uuid_string"))]
+    pub folder_id: Option<String>,
+    /// Also search the subfolders of `folderId` (defaults to false)
+    pub include_subfolders: Option<bool>,
+    /// Also match similar words and typos (defaults to false)
+    pub fuzzy: Option<bool>,
+    /// Optional color
+    pub color: Option<String>,
+    /// Optional icon
+    pub icon: Option<String>,
+    /// Optional position in the list
+    pub position: Option<i32>,
+}
+
+/// 🔖 Input for updating a saved search; omitted fields are left unchanged
+#[derive(InputObject, Validate)]
+pub struct UpdateSavedSearchInput {
+    /// Optional new name
+    #[validate(
+        custom(function = "not_blank", message = "Name cannot be empty"),
+        length(
+            max = "FOLDER_NAME_MAX_LENGTH",
+            message = "Name must be at most 100 characters"
+        )
+    )]
+    pub name: Option<String>,
+    /// Optional new query
+    #[validate(
+        custom(function = "not_blank", message = "Search query cannot be empty"),
+        length(
+            max = "SEARCH_QUERY_MAX_LENGTH",
+            message = "Search query must be at most 500 characters"
+        )
+    )]
+    pub query: Option<String>,
+    /// Optional new folder to search; an empty string searches all folders again
+    #[validate(custom(function = "uuid_or_empty"))]
+    pub folder_id: Option<String>,
+    /// Optional new subfolder setting
+    pub include_subfolders: Option<bool>,
+    /// Optional new fuzzy setting
+    pub fuzzy: Option<bool>,
+    /// Optional new color
+    pub color: Option<String>,
+    /// Optional new icon
+    pub icon: Option<String>,
+    /// Optional new position
+    pub position: Option<i32>,
+}
+
+/// 🧩 Note template with `{{placeholder}}` markers (see `createNoteFromTemplate`)
+#[derive(SimpleObject, Clone)]
+pub struct NoteTemplate {
+    /// Unique identifier (UUID as string)
+    pub id: String,
+    /// Template name
+    pub name: String,
+    /// Template description, if any
+    pub description: Option<String>,
+    /// Title of created notes; `null` auto-generates it from the content
+    pub title_template: Option<String>,
+    /// Content of created notes
+    pub content_template: String,
+    /// Can every user create notes from this template?
+    pub is_shared: bool,
+    /// Does the current user own (and may change) this template?
+    pub is_own: bool,
+    /// Placeholders that need a value in `variables`, in order of appearance
+    pub prompts: Vec<String>,
+    /// Creation timestamp (RFC3339 format)
+    pub created_at: String,
+    /// Last update timestamp (RFC3339 format)
+    pub updated_at: String,
+}
+
+/// 🧩 Input for creating a note template
+#[derive(InputObject, Validate)]
+pub struct CreateNoteTemplateInput {
+    /// Template name (required)
+    #[validate(
+        custom(function = "not_blank", message = "Name cannot be empty"),
+        length(
+            max = "FOLDER_NAME_MAX_LENGTH",
+            message = "Name must be at most 100 characters"
+        )
+    )]
+    pub name: String,
+    /// Optional description
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+    /// Optional title; notes get an auto-generated title when omitted
+    #[validate(length(
+        max = "TITLE_MAX_LENGTH",
+        message = "Title must be at most 200 characters"
+    ))]
+    pub title_template: Option<String>,
+    /// Note content with placeholders (required)
+    #[validate(custom(function = "not_blank", message = "Content cannot be empty"))]
+    pub content_template: String,
+    /// Let every user use the template (defaults to false)
+    pub is_shared: Option<bool>,
+}
+
+/// 🧩 Input for updating a note template; omitted fields are left unchanged
+#[derive(InputObject, Validate)]
+pub struct UpdateNoteTemplateInput {
+    /// Optional new name
+    #[validate(
+        custom(function = "not_blank", message = "Name cannot be empty"),
+        length(
+            max = "FOLDER_NAME_MAX_LENGTH",
+            message = "Name must be at most 100 characters"
+        )
+    )]
+    pub name: Option<String>,
+    /// Optional new description; an empty string removes it
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+    /// Optional new title; an empty string switches to auto-generated titles
+    #[validate(length(
+        max = "TITLE_MAX_LENGTH",
+        message = "Title must be at most 200 characters"
+    ))]
+    pub title_template: Option<String>,
+    /// Optional new content
+    #[validate(custom(function = "not_blank", message = "Content cannot be empty"))]
+    pub content_template: Option<String>,
+    /// Optional new sharing setting
+    pub is_shared: Option<bool>,
+}
+
+/// 🧩 Value for one of a template's prompts
+#[derive(InputObject)]
+pub struct TemplateVariableInput {
+    /// Placeholder name, as listed in `prompts`
+    pub name: String,
+    /// Text to put in its place
+    pub value: String,
+}
+
+/// 👑 System-wide statistics for administrators
+#[derive(SimpleObject)]
+pub struct SystemStats {
+    /// Total number of registered users
+    pub total_users: i64,
+    /// Number of active (not deactivated) users
+    pub active_users: i64,
+    /// Number of users with the ADMIN role
+    pub admin_users: i64,
+    /// Total number of notes across all users
+    pub total_notes: i64,
+    /// Notes created in the last 7 days
+    pub notes_last_7_days: i64,
+    /// Total number of folders across all users
+    pub total_folders: i64,
+}
+
+/// 📦 Machine-readable export of a user's personal data
+#[derive(SimpleObject)]
+pub struct DataExport {
+    /// When the export was generated (RFC3339 format)
+    pub exported_at: String,
+    /// Profile, notes, folders, saved searches, login history and activity
+    pub data: async_graphql::Json<serde_json::Value>,
+}
+
+/// 🕵️ Entry of the activity feed and audit log
+#[derive(SimpleObject, Clone)]
+pub struct AuditEvent {
+    /// Unique identifier (UUID as string)
+    pub id: String,
+    /// Account the event belongs to; `null` for logins with an unknown email
+    pub user_id: Option<String>,
+    /// Who performed the action: the user, or an administrator
+    pub actor_id: Option<String>,
+    /// What happened
+    pub action: AuditAction,
+    /// Kind of object the action was applied to
+    pub target_type: Option<AuditTargetType>,
+    /// ID of that object
+    pub target_id: Option<String>,
+    /// Client IP address
+    pub ip_address: Option<String>,
+    /// Client user agent
+    pub user_agent: Option<String>,
+    /// Changed fields as `{"field": {"from": ..., "to": ...}}`
+    pub changes: Option<async_graphql::Json<serde_json::Value>>,
+    /// When it happened (RFC3339 format)
+    pub created_at: String,
+}
+
+/// 🧰 Filters for the admin `auditEvents` query; every given condition must match
+#[derive(InputObject, Validate, Default)]
+pub struct AuditEventFilter {
+    /// Only events of this account
+    #[validate(custom(function = "uuid_string"))]
+    pub user_id: Option<String>,
+    /// Only events performed by this user
+    #[validate(custom(function = "uuid_string"))]
+    pub actor_id: Option<String>,
+    /// Only these actions
+    pub actions: Option<Vec<AuditAction>>,
+    /// Only events about this kind of object
+    pub target_type: Option<AuditTargetType>,
+    /// Only events about this object
+    #[validate(custom(function = "uuid_string"))]
+    pub target_id: Option<String>,
+    /// Only events from this IP address
+    pub ip_address: Option<String>,
+    /// Events at or after this time (RFC3339 format)
+    pub after: Option<String>,
+    /// Events before this time (RFC3339 format)
+    pub before: Option<String>,
+}
+
+/// Reject empty or whitespace-only strings
+fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("blank"));
+    }
+    Ok(())
+}
+
+/// Require a UUID string for ID fields
+fn uuid_string(value: &str) -> Result<(), ValidationError> {
+    uuid::Uuid::parse_str(value).map(|_| ()).map_err(|_| {
+        ValidationError::new("invalid_uuid").with_message("Must be a valid UUID".into())
+    })
+}
+
+/// Require a UUID string or an empty string (clears the ID)
+fn uuid_or_empty(value: &str) -> Result<(), ValidationError> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    uuid_string(value)
 }
@@ -0,0 +1,123 @@
+//! # Note View Tracking
+//!
+//! Opening a note through the `note` query records a view in the
+//! [`ViewTracker`] buffer, in memory. A background job
+//! ([`crate::jobs::spawn_view_flush`]) writes the buffer in one batch: the
+//! `notes.view_count` increments and each user's `note_views` log of when they
+//! last opened a note. Reads never wait on a write.
+//!
+//! Views still buffered are flushed on graceful shutdown, and lost if the
+//! process crashes. `recentlyViewedNotes` merges the buffer with the log, so a
+//! note shows up there as soon as it is opened; `viewCount` catches up at the
+//! next flush.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::errors::AppResult;
+
+/// Views recorded since the last flush
+#[derive(Debug, Default)]
+pub struct PendingViews {
+    /// New views per note
+    pub counts: HashMap<Uuid, i32>,
+    /// Latest view per (user, note)
+    pub last_viewed: HashMap<(Uuid, Uuid), DateTime<Utc>>,
+}
+
+impl PendingViews {
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Fold `other` (older views) back into this buffer
+    fn merge(&mut self, other: PendingViews) {
+        for (note_id, count) in other.counts {
+            *self.counts.entry(note_id).or_default() += count;
+        }
+        for (key, viewed_at) in other.last_viewed {
+            let latest = self.last_viewed.entry(key).or_insert(viewed_at);
+            *latest = (*latest).max(viewed_at);
+        }
+    }
+}
+
+/// 👀 Buffer of note views shared by the resolvers and the flush job
+#[derive(Clone, Default)]
+pub struct ViewTracker {
+    pending: Arc<Mutex<PendingViews>>,
+}
+
+impl ViewTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn pending(&self) -> MutexGuard<'_, PendingViews> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Record that `user_id` opened `note_id`
+    pub fn record(&self, user_id: Uuid, note_id: Uuid) {
+        let mut pending = self.pending();
+        *pending.counts.entry(note_id).or_default() += 1;
+        pending.last_viewed.insert((user_id, note_id), Utc::now());
+    }
+
+    /// Buffered views of one user, as (note, viewed at)
+    pub fn pending_for_user(&self, user_id: Uuid) -> Vec<(Uuid, DateTime<Utc>)> {
+        self.pending()
+            .last_viewed
+            .iter()
+            .filter(|((viewer, _), _)| *viewer == user_id)
+            .map(|((_, note_id), viewed_at)| (*note_id, *viewed_at))
+            .collect()
+    }
+
+    /// Write the buffered views, returning how many were written
+    ///
+    /// On failure the views go back into the buffer for the next attempt.
+    pub async fn flush(&self, db: &Database) -> AppResult<i64> {
+        let batch = std::mem::take(&mut *self.pending());
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        let views = batch.counts.values().map(|count| i64::from(*count)).sum();
+
+        if let Err(e) = db.record_note_views(&batch).await {
+            self.pending().merge(batch);
+            return Err(e);
+        }
+        Ok(views)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_views_are_counted_per_note_and_logged_per_user() {
+        let tracker = ViewTracker::new();
+        let (alice, bob, note) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        tracker.record(alice, note);
+        tracker.record(alice, note);
+        tracker.record(bob, note);
+
+        assert_eq!(tracker.pending().counts[&note], 3);
+        assert_eq!(tracker.pending_for_user(alice).len(), 1);
+
+        // A failed flush puts its views back alongside newer ones
+        let batch = std::mem::take(&mut *tracker.pending());
+        tracker.record(alice, note);
+        tracker.pending().merge(batch);
+        assert_eq!(tracker.pending().counts[&note], 4);
+        assert_eq!(tracker.pending().last_viewed.len(), 2);
+    }
+}
@@ -1,33 +1,263 @@
 //! # GraphQL Web Handlers with JWT Support
 //!
 //! Pure Axum implementation with JWT authentication
+//!
+//! ## GraphQL over HTTP
+//!
+//! - `POST /graphql` with a JSON object, or a JSON array of objects executed
+//!   in order as a batch (up to `limits.max_batch_size` operations)
+//! - `GET /graphql?query=...&variables=...&operationName=...&extensions=...`
+//!   for cacheable queries; mutations are rejected with `405`
+//! - Responses use `application/graphql-response+json` when the client accepts
+//!   it, with `400` for requests that could not be executed. Otherwise they use
+//!   `application/json` and `200`, as before.
 
+use async_graphql::parser::{parse_query, types::OperationType};
 use async_graphql::{http::GraphiQLSource, Schema, Variables};
 use axum::{
-    extract::{FromRequest, Json, Request, State}, // ✅ Add FromRequest import
-    http::StatusCode,
-    response::{Html, IntoResponse, Json as JsonResponse},
+    body::Bytes,
+    extract::{ConnectInfo, FromRequest, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{Html, IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use tracing::Instrument;
 
 use crate::auth::AuthContext;
+use crate::errors::AppError;
+use crate::persisted_queries::PersistedQueries;
 use crate::resolvers::{MutationRoot, QueryRoot, SubscriptionRoot};
 
 pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
+/// Media type defined by the GraphQL-over-HTTP specification
+const GRAPHQL_RESPONSE_JSON: &str = "application/graphql-response+json";
+
+/// Shared state for the GraphQL endpoint
+#[derive(Clone)]
+pub struct GraphQLState {
+    pub schema: AppSchema,
+    pub persisted_queries: PersistedQueries,
+    /// Most operations accepted in one batched request
+    pub max_batch_size: usize,
+    /// Peers whose `X-Forwarded-For` header is believed
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Serve the SDL at `/schema.graphql`
+    pub expose_schema: bool,
+}
+
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GraphQLRequest {
-    pub query: String,
+    /// Optional when a persisted query hash is sent in `extensions`
+    pub query: Option<String>,
     pub variables: Option<serde_json::Value>,
+    #[serde(alias = "operation_name")]
     pub operation_name: Option<String>,
+    /// Protocol extensions such as `persistedQuery`
+    pub extensions: Option<serde_json::Value>,
 }
 
+/// `GET /graphql` query string, with `variables` and `extensions` JSON-encoded
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphQLGetParams {
+    query: Option<String>,
+    variables: Option<String>,
+    #[serde(alias = "operation_name")]
+    operation_name: Option<String>,
+    extensions: Option<String>,
+}
+
+impl GraphQLGetParams {
+    /// Decode the JSON-encoded parameters into a regular request
+    fn into_request(self) -> Result<GraphQLRequest, String> {
+        let decode = |name: &str, value: Option<String>| {
+            value
+                .filter(|value| !value.is_empty())
+                .map(|value| serde_json::from_str(&value))
+                .transpose()
+                .map_err(|e| format!("Invalid {}: {}", name, e))
+        };
+
+        Ok(GraphQLRequest {
+            query: self.query,
+            variables: decode("variables", self.variables)?,
+            operation_name: self.operation_name,
+            extensions: decode("extensions", self.extensions)?,
+        })
+    }
+}
+
+/// Request body: a single operation or a batch
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GraphQLBody {
+    Single(GraphQLRequest),
+    Batch(Vec<GraphQLRequest>),
+}
+
+/// Spec-shaped response body
+///
+/// `data` is omitted for requests that never reached execution, and `errors`
+/// is omitted when there are none. Each error keeps its `locations`, `path`
+/// and `extensions` (including the stable `code`).
 #[derive(Serialize)]
 pub struct GraphQLResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub errors: Option<Vec<serde_json::Value>>,
 }
 
+impl GraphQLResponse {
+    /// Response for a request that failed before execution
+    fn request_error(error: serde_json::Value) -> Self {
+        Self {
+            data: None,
+            errors: Some(vec![error]),
+        }
+    }
+}
+
+/// Response media type chosen from the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseFormat {
+    /// `application/graphql-response+json`: status codes reflect request errors
+    GraphQLResponseJson,
+    /// `application/json`: always `200` once the request could be parsed
+    Json,
+}
+
+impl ResponseFormat {
+    /// Pick a format the client accepts, preferring the GraphQL media type
+    fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let Some(accept) = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Some(ResponseFormat::Json);
+        };
+
+        let accepted = accept
+            .split(',')
+            .filter_map(|range| {
+                let mut params = range.split(';').map(str::trim);
+                let media_type = params.next()?.to_ascii_lowercase();
+                let rejected = params.any(|param| {
+                    param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0)
+                });
+                (!rejected).then_some(media_type)
+            })
+            .collect::<Vec<_>>();
+
+        if accepted.iter().any(|media| media == GRAPHQL_RESPONSE_JSON) {
+            Some(ResponseFormat::GraphQLResponseJson)
+        } else if accepted
+            .iter()
+            .any(|media| matches!(media.as_str(), "application/json" | "application/*" | "*/*"))
+        {
+            Some(ResponseFormat::Json)
+        } else {
+            None
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ResponseFormat::GraphQLResponseJson => GRAPHQL_RESPONSE_JSON,
+            ResponseFormat::Json => "application/json",
+        }
+    }
+
+    /// Status for an outcome; legacy JSON clients only see method errors
+    fn status(self, status: StatusCode) -> StatusCode {
+        match self {
+            ResponseFormat::GraphQLResponseJson => status,
+            ResponseFormat::Json if status == StatusCode::METHOD_NOT_ALLOWED => status,
+            ResponseFormat::Json => StatusCode::OK,
+        }
+    }
+
+    /// Serialize a response body with this format's content type
+    fn respond<T: Serialize>(self, status: StatusCode, body: &T) -> Response {
+        let body = serde_json::to_vec(body).unwrap_or_default();
+        (
+            status,
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(self.content_type()),
+            )],
+            body,
+        )
+            .into_response()
+    }
+}
+
+/// 🌐 Information about the calling client, available to resolvers
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    /// Client IP (see [`client_ip`])
+    pub ip_address: Option<String>,
+    /// `User-Agent` header value
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    /// Extract client information from request headers and connection info
+    fn from_request(request: &Request, trusted_proxies: &[IpAddr]) -> Self {
+        let headers = request.headers();
+
+        let ip_address = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| client_ip(addr.ip(), headers, trusted_proxies).to_string());
+
+        let user_agent = headers
+            .get("user-agent")
+            .and_then(|h| h.to_str().ok())
+            .map(|ua| ua.to_string());
+
+        Self {
+            ip_address,
+            user_agent,
+        }
+    }
+}
+
+/// The client's IP address
+///
+/// `X-Forwarded-For` is only believed when the connecting peer is a trusted
+/// proxy. The chain is then walked from the nearest hop back, and the first
+/// address that is not one of our proxies is the client; anything to the left
+/// of it was written by the client and may be forged.
+fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !trusted_proxies.contains(&ip) {
+            break;
+        }
+    }
+    client
+}
+
 /// Convert serde_json::Value to async_graphql::Variables
 fn convert_variables(value: serde_json::Value) -> Result<Variables, serde_json::Error> {
     Ok(Variables::from_json(value))
@@ -38,37 +268,159 @@ fn convert_value(value: async_graphql::Value) -> serde_json::Value {
     serde_json::to_value(value).unwrap_or_default()
 }
 
-/// 🔐 GraphQL handler with JWT authentication
-pub async fn graphql_handler(
-    State(schema): State<AppSchema>,
-    request: Request,
-) -> impl IntoResponse {
+/// Whether the operation that would be executed is a mutation
+///
+/// Documents that fail to parse or select no operation are left for the
+/// executor to report.
+fn is_mutation(query: &str, operation_name: Option<&str>) -> bool {
+    let Ok(document) = parse_query(query) else {
+        return false;
+    };
+
+    let mut operations = document.operations.iter();
+    let selected = match operation_name {
+        Some(name) => operations.find(|(op_name, _)| op_name.map(|n| n.as_str()) == Some(name)),
+        None => operations.next().filter(|_| operations.next().is_none()),
+    };
+
+    selected.is_some_and(|(_, op)| op.node.ty == OperationType::Mutation)
+}
+
+/// 🔐 GraphQL handler with JWT authentication (GET and POST)
+pub async fn graphql_handler(State(state): State<GraphQLState>, request: Request) -> Response {
     // Extract auth context from middleware
     let auth_context = request
         .extensions()
         .get::<AuthContext>()
         .cloned()
         .unwrap_or_else(AuthContext::unauthenticated);
+    let client_info = ClientInfo::from_request(&request, &state.trusted_proxies);
 
-    // ✅ Fix: Extract JSON body properly with Axum 0.7
-    let (parts, body) = request.into_parts();
-    let request_with_body = Request::from_parts(parts, body);
+    let Some(format) = ResponseFormat::negotiate(request.headers()) else {
+        return (
+            StatusCode::NOT_ACCEPTABLE,
+            format!(
+                "Supported response types: {}, application/json",
+                GRAPHQL_RESPONSE_JSON
+            ),
+        )
+            .into_response();
+    };
 
-    let Json(graphql_request): Json<GraphQLRequest> =
-        match Json::from_request(request_with_body, &()).await {
-            Ok(json) => json,
-            Err(e) => {
-                let error_response = GraphQLResponse {
-                    data: None,
-                    errors: Some(vec![
-                        serde_json::json!({"message": format!("Invalid JSON: {}", e)}),
-                    ]),
-                };
-                return (StatusCode::BAD_REQUEST, JsonResponse(error_response));
+    // GET: a single operation encoded in the query string
+    if request.method() == Method::GET {
+        let graphql_request = match Query::<GraphQLGetParams>::try_from_uri(request.uri())
+            .map_err(|e| format!("Invalid query string: {}", e))
+            .and_then(|Query(params)| params.into_request())
+        {
+            Ok(graphql_request) => graphql_request,
+            Err(message) => {
+                return format.respond(
+                    StatusCode::BAD_REQUEST,
+                    &GraphQLResponse::request_error(serde_json::json!({ "message": message })),
+                );
             }
         };
 
-    let mut req = async_graphql::Request::new(graphql_request.query);
+        let (status, body) =
+            execute_operation(&state, graphql_request, &auth_context, &client_info, true).await;
+        let mut response = format.respond(format.status(status), &body);
+        if status == StatusCode::METHOD_NOT_ALLOWED {
+            response
+                .headers_mut()
+                .insert(header::ALLOW, HeaderValue::from_static("POST"));
+        }
+        // Cached responses differ by negotiated format and caller
+        response.headers_mut().append(
+            header::VARY,
+            HeaderValue::from_static("accept, authorization"),
+        );
+        return response;
+    }
+
+    // POST: a JSON object, or an array of them for a batch
+    let body = match Bytes::from_request(request, &()).await {
+        Ok(body) => body,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let graphql_body: GraphQLBody = match serde_json::from_slice(&body) {
+        Ok(graphql_body) => graphql_body,
+        Err(e) => {
+            return format.respond(
+                StatusCode::BAD_REQUEST,
+                &GraphQLResponse::request_error(
+                    serde_json::json!({"message": format!("Invalid JSON: {}", e)}),
+                ),
+            );
+        }
+    };
+
+    match graphql_body {
+        GraphQLBody::Single(graphql_request) => {
+            let (status, body) =
+                execute_operation(&state, graphql_request, &auth_context, &client_info, false)
+                    .await;
+            format.respond(format.status(status), &body)
+        }
+        GraphQLBody::Batch(requests) => {
+            if requests.is_empty() || requests.len() > state.max_batch_size {
+                let message = format!(
+                    "Batch must contain between 1 and {} operations, got {}",
+                    state.max_batch_size,
+                    requests.len()
+                );
+                return format.respond(
+                    StatusCode::BAD_REQUEST,
+                    &GraphQLResponse::request_error(serde_json::json!({ "message": message })),
+                );
+            }
+
+            // Executed in order so a batch can mix dependent mutations and queries
+            let mut responses = Vec::with_capacity(requests.len());
+            for graphql_request in requests {
+                let (_, body) =
+                    execute_operation(&state, graphql_request, &auth_context, &client_info, false)
+                        .await;
+                responses.push(body);
+            }
+            format.respond(StatusCode::OK, &responses)
+        }
+    }
+}
+
+/// Execute one operation, returning its GraphQL-over-HTTP status and body
+async fn execute_operation(
+    state: &GraphQLState,
+    graphql_request: GraphQLRequest,
+    auth_context: &AuthContext,
+    client_info: &ClientInfo,
+    is_get: bool,
+) -> (StatusCode, GraphQLResponse) {
+    // 📌 Resolve persisted query hashes to the query document
+    let query = match state
+        .persisted_queries
+        .resolve(graphql_request.query, graphql_request.extensions.as_ref())
+    {
+        Ok(query) => query,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                GraphQLResponse::request_error(e.to_json()),
+            )
+        }
+    };
+
+    // GET must be safe: mutations are only accepted over POST
+    if is_get && is_mutation(&query, graphql_request.operation_name.as_deref()) {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            GraphQLResponse::request_error(serde_json::json!({
+                "message": "Mutations are only allowed over POST",
+            })),
+        );
+    }
+
+    let mut req = async_graphql::Request::new(query);
 
     // Convert variables if present
     if let Some(variables_json) = graphql_request.variables {
@@ -77,46 +429,71 @@ pub async fn graphql_handler(
                 req = req.variables(vars);
             }
             Err(e) => {
-                let error_response = GraphQLResponse {
-                    data: None,
-                    errors: Some(vec![
+                return (
+                    StatusCode::BAD_REQUEST,
+                    GraphQLResponse::request_error(
                         serde_json::json!({"message": format!("Invalid variables: {}", e)}),
-                    ]),
-                };
-                return (StatusCode::BAD_REQUEST, JsonResponse(error_response));
+                    ),
+                );
             }
         }
     }
 
+    // 📜 One span per GraphQL operation, nested in the HTTP request span
+    let span = tracing::info_span!(
+        "graphql",
+        operation_name = graphql_request.operation_name.as_deref().unwrap_or("anonymous"),
+        user_id = ?auth_context.user_id,
+    );
+
     if let Some(operation_name) = graphql_request.operation_name {
         req = req.operation_name(operation_name);
     }
 
-    // 🔐 Add auth context to GraphQL request
-    req = req.data(auth_context);
+    // 🔐 Add auth context and client info to GraphQL request
+    req = req.data(auth_context.clone()).data(client_info.clone());
+
+    let response = state.schema.execute(req).instrument(span.clone()).await;
 
-    let response = schema.execute(req).await;
+    span.in_scope(|| {
+        for error in &response.errors {
+            let code = error
+                .extensions
+                .as_ref()
+                .and_then(|ext| ext.get("code"))
+                .and_then(|code| match code {
+                    async_graphql::Value::String(code) => Some(code.as_str()),
+                    _ => None,
+                });
+            // Unredacted message, for errors whose client message hides details
+            let detail = error.source::<AppError>().map(|e| e.to_string());
+            tracing::warn!(code = code.unwrap_or_default(), path = ?error.path, detail = ?detail, "{}", error.message);
+        }
+    });
+
+    // Parse, validation and limit errors never start execution: `data` stays
+    // null and, unlike resolver errors, the errors have no path
+    let request_error = !response.errors.is_empty()
+        && response.data == async_graphql::Value::Null
+        && response.errors.iter().all(|error| error.path.is_empty());
 
     let json_response = GraphQLResponse {
-        data: if response.errors.is_empty() {
-            Some(convert_value(response.data))
-        } else {
-            None
-        },
-        errors: if response.errors.is_empty() {
-            None
-        } else {
-            Some(
-                response
-                    .errors
-                    .into_iter()
-                    .map(|e| serde_json::json!({"message": e.message, "path": e.path}))
-                    .collect(),
-            )
-        },
+        data: (!request_error).then(|| convert_value(response.data)),
+        errors: (!response.errors.is_empty()).then(|| {
+            response
+                .errors
+                .iter()
+                .map(|error| serde_json::to_value(error).unwrap_or_default())
+                .collect()
+        }),
     };
 
-    (StatusCode::OK, JsonResponse(json_response))
+    let status = if request_error {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    };
+    (status, json_response)
 }
 
 /// Real Interactive GraphiQL Interface!
@@ -129,6 +506,22 @@ pub async fn graphiql() -> impl IntoResponse {
     )
 }
 
+/// 📜 Schema SDL for client code generation (`GET /schema.graphql`)
+///
+/// Answers 404 unless `server.expose_schema` is on, which it is not by
+/// default when only allowlisted operations may run.
+pub async fn schema_sdl(State(state): State<GraphQLState>) -> Response {
+    if !state.expose_schema {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        state.schema.sdl(),
+    )
+        .into_response()
+}
+
 /// Beautiful Landing Page with JWT Authentication Info
 pub async fn landing_page() -> impl IntoResponse {
     Html(
@@ -293,3 +686,196 @@ mutation {
 "#,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::EmptySubscription;
+
+    use super::*;
+    use crate::config::PersistedQueriesConfig;
+
+    /// Endpoint state around a schema without data (enough for requests
+    /// that fail before reaching a resolver)
+    fn test_state() -> GraphQLState {
+        GraphQLState {
+            schema: Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish(),
+            persisted_queries: PersistedQueries::from_config(&PersistedQueriesConfig::default())
+                .unwrap(),
+            max_batch_size: 2,
+            trusted_proxies: Vec::new(),
+            expose_schema: true,
+        }
+    }
+
+    /// Run `request` through the GraphQL endpoint, returning status, headers and JSON body
+    async fn call(request: Request) -> (StatusCode, HeaderMap, serde_json::Value) {
+        let response = graphql_handler(State(test_state()), request).await;
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let json = serde_json::from_slice(&body).unwrap_or_default();
+        (parts.status, parts.headers, json)
+    }
+
+    fn accepting(accept: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(accept).unwrap());
+        headers
+    }
+
+    fn post_batch(operations: usize) -> Request {
+        let batch = vec![serde_json::json!({ "query": "{ hello }" }); operations];
+        Request::builder()
+            .method(Method::POST)
+            .uri("/graphql")
+            .header(header::ACCEPT, GRAPHQL_RESPONSE_JSON)
+            .body(serde_json::to_vec(&batch).unwrap().into())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_accept_negotiation() {
+        use ResponseFormat::{GraphQLResponseJson, Json};
+
+        assert_eq!(ResponseFormat::negotiate(&HeaderMap::new()), Some(Json));
+        assert_eq!(
+            ResponseFormat::negotiate(&accepting("application/json")),
+            Some(Json)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(&accepting("application/graphql-response+json")),
+            Some(GraphQLResponseJson)
+        );
+        assert_eq!(ResponseFormat::negotiate(&accepting("*/*")), Some(Json));
+        assert_eq!(
+            ResponseFormat::negotiate(&accepting("text/html, application/*;q=0.8")),
+            Some(Json)
+        );
+        // The GraphQL media type wins whenever it is acceptable at all
+        assert_eq!(
+            ResponseFormat::negotiate(&accepting(
+                "application/json, application/graphql-response+json;q=0.5"
+            )),
+            Some(GraphQLResponseJson)
+        );
+        // q=0 means "not acceptable"
+        assert_eq!(
+            ResponseFormat::negotiate(&accepting("application/graphql-response+json; q=0, */*")),
+            Some(Json)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(&accepting("application/json;q=0.0")),
+            None
+        );
+        assert_eq!(ResponseFormat::negotiate(&accepting("text/html")), None);
+    }
+
+    #[test]
+    fn test_is_mutation() {
+        assert!(is_mutation("mutation { logout }", None));
+        assert!(!is_mutation("{ me { id } }", None));
+        assert!(!is_mutation("query Me { me { id } }", Some("Me")));
+
+        let document = "query Me { me { id } } mutation Out { logout }";
+        assert!(is_mutation(document, Some("Out")));
+        assert!(!is_mutation(document, Some("Me")));
+        // Ambiguous or invalid documents are reported by the executor
+        assert!(!is_mutation(document, None));
+        assert!(!is_mutation("mutation {", None));
+    }
+
+    #[tokio::test]
+    async fn test_mutations_over_get_are_rejected() {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/graphql?query=mutation%20%7B%20logout%20%7D")
+            .header(header::ACCEPT, GRAPHQL_RESPONSE_JSON)
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        let (status, headers, body) = call(request).await;
+
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(headers[header::ALLOW], "POST");
+        assert_eq!(
+            body["errors"][0]["message"],
+            "Mutations are only allowed over POST"
+        );
+        assert!(body.get("data").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_empty_and_oversized_batches_are_rejected() {
+        let (status, _, body) = call(post_batch(0)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["errors"][0]["message"],
+            "Batch must contain between 1 and 2 operations, got 0"
+        );
+
+        // test_state() allows two operations per batch
+        let (status, _, body) = call(post_batch(3)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["errors"][0]["message"],
+            "Batch must contain between 1 and 2 operations, got 3"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partial_data_keeps_errors_with_code_and_locations() {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/graphql")
+            .header(header::ACCEPT, GRAPHQL_RESPONSE_JSON)
+            .body(r#"{"query": "{ hello\n  note(id: \"1\") { id } }"}"#.into())
+            .unwrap();
+
+        let (status, _, body) = call(request).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["data"]["hello"].is_string());
+        assert_eq!(body["data"]["note"], serde_json::Value::Null);
+        let error = &body["errors"][0];
+        assert_eq!(error["extensions"]["code"], "UNAUTHORIZED");
+        assert_eq!(
+            error["locations"],
+            serde_json::json!([{ "line": 2, "column": 3 }])
+        );
+        assert_eq!(error["path"], serde_json::json!(["note"]));
+    }
+
+    #[tokio::test]
+    async fn test_schema_sdl_is_hidden_unless_exposed() {
+        let exposed = schema_sdl(State(test_state())).await;
+        assert_eq!(exposed.status(), StatusCode::OK);
+
+        let hidden = schema_sdl(State(GraphQLState {
+            expose_schema: false,
+            ..test_state()
+        }))
+        .await;
+        assert_eq!(hidden.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_forwarded_for_is_only_believed_from_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let peer: IpAddr = "203.0.113.9".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("1.2.3.4, 198.51.100.7, 10.0.0.1"),
+        );
+        let trusted = ["10.0.0.1".parse().unwrap(), proxy];
+
+        // Untrusted peers cannot pick their own address
+        assert_eq!(client_ip(peer, &headers, &trusted), peer);
+        assert_eq!(client_ip(proxy, &headers, &[]), proxy);
+        // Behind our proxies, the nearest untrusted hop is the client
+        assert_eq!(
+            client_ip(proxy, &headers, &trusted),
+            "198.51.100.7".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(client_ip(proxy, &HeaderMap::new(), &trusted), proxy);
+    }
+}